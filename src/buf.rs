@@ -0,0 +1,475 @@
+//! Owned blockquote formatter.
+
+use super::{
+    AlertKind, BacktickPolicy, BidiPolicy, Blockquote, Boundary, ControlPolicy, EscapeProfile,
+    Format, HeadingPolicy, ImagePolicy, LimitOverflow, LimitUnit, LineEnding, MinCharsPolicy,
+    NestingStyle, PrefixMode, Prefixes, PreparedBlockquote, Source,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    fmt::{Display, Error as FmtError, Formatter},
+    iter::FromIterator,
+};
+
+/// Define builder methods delegating to those of [`Blockquote`].
+macro_rules! delegate {
+    ($($name:ident($arg:ident: $ty:ty)),* $(,)?) => {
+        $(
+            #[doc = concat!("See [`Blockquote::", stringify!($name), "`].")]
+            pub fn $name(mut self, $arg: $ty) -> Self {
+                self.config = self.config.$name($arg);
+
+                self
+            }
+        )*
+    };
+}
+
+/// Quote some owned text in a markdown blockquote.
+///
+/// Configured and formatted identically to [`Blockquote`], but owns its text
+/// and configuration, so it can be stored without borrowing. Create one with
+/// [`new`] or [`Blockquote::to_owned`].
+///
+/// # Examples
+///
+/// ```
+/// use markdown_blockquote_formatter::BlockquoteBuf;
+///
+/// let blockquote = BlockquoteBuf::new(String::from("hey, this is cool!")).soft_limit(9);
+///
+/// assert_eq!(blockquote.to_string(), "> hey, this…");
+/// ```
+///
+/// [`new`]: Self::new
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BlockquoteBuf {
+    /// Kind of a custom alert.
+    alert: String,
+    alternating_prefixes: Vec<String>,
+    attribution: Option<String>,
+    attribution_link: Option<String>,
+    /// Summary of a collapsible section.
+    collapsible: Option<String>,
+    /// Configuration other than the text and strings.
+    config: Blockquote<'static>,
+    ellipsis_line_marker: Option<String>,
+    empty_line_fill: String,
+    /// Language of a fenced code block.
+    fenced: Option<String>,
+    filter_lines_starting_with: Option<String>,
+    first_line_prefix: Option<String>,
+    header: Option<String>,
+    indent: String,
+    plain_indent: String,
+    text: String,
+    timestamp: Option<String>,
+    truncation_suffix: String,
+    whitespace_chars: String,
+    wrap_continuation: Option<String>,
+}
+
+impl BlockquoteBuf {
+    /// Create a new owned markdown blockquote formatter.
+    pub fn new(text: impl Into<String>) -> Self {
+        Blockquote::new("").to_owned().text(text)
+    }
+
+    /// Create an owned copy of a blockquote.
+    pub(crate) fn from_blockquote(blockquote: &Blockquote<'_>) -> Self {
+        let text = match blockquote.source {
+            Source::Lines(lines) => lines.join("\n"),
+            Source::Text(text) => text.to_string(),
+        };
+
+        let alert = match blockquote.alert {
+            Some(AlertKind::Custom(kind)) => kind.to_string(),
+            _ => String::new(),
+        };
+
+        Self {
+            alert,
+            alternating_prefixes: blockquote
+                .alternating_prefixes
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            attribution: blockquote.attribution.map(ToString::to_string),
+            attribution_link: blockquote.attribution_link.map(ToString::to_string),
+            collapsible: blockquote.collapsible.map(ToString::to_string),
+            config: Blockquote {
+                alert: blockquote.alert.map(owned_alert),
+                alternating_prefixes: Prefixes::Borrowed(&[]),
+                attribution: None,
+                attribution_link: None,
+                collapsible: None,
+                ellipsis_line_marker: None,
+                empty_line_fill: "",
+                fenced: None,
+                filter_lines_starting_with: None,
+                first_line_prefix: None,
+                header: None,
+                indent: "",
+                plain_indent: "",
+                source: Source::Text(""),
+                timestamp: None,
+                truncation_suffix: "",
+                whitespace_chars: "",
+                wrap_continuation: None,
+                ..*blockquote
+            },
+            ellipsis_line_marker: blockquote.ellipsis_line_marker.map(ToString::to_string),
+            empty_line_fill: blockquote.empty_line_fill.to_string(),
+            fenced: blockquote.fenced.map(ToString::to_string),
+            filter_lines_starting_with: blockquote
+                .filter_lines_starting_with
+                .map(ToString::to_string),
+            first_line_prefix: blockquote.first_line_prefix.map(ToString::to_string),
+            header: blockquote.header.map(ToString::to_string),
+            indent: blockquote.indent.to_string(),
+            plain_indent: blockquote.plain_indent.to_string(),
+            text,
+            timestamp: blockquote.timestamp.map(ToString::to_string),
+            truncation_suffix: blockquote.truncation_suffix.to_string(),
+            whitespace_chars: blockquote.whitespace_chars.to_string(),
+            wrap_continuation: blockquote.wrap_continuation.map(ToString::to_string),
+        }
+    }
+
+    /// Borrowing blockquote formatter with the same text and configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::BlockquoteBuf;
+    ///
+    /// let blockquote = BlockquoteBuf::new("hey, this is cool!");
+    ///
+    /// assert!(blockquote.as_blockquote().would_truncate_at(9, None));
+    /// ```
+    pub fn as_blockquote(&self) -> Blockquote<'_> {
+        Blockquote {
+            alert: self.config.alert.map(|alert| match alert {
+                AlertKind::Custom(_) => AlertKind::Custom(&self.alert),
+                alert => alert,
+            }),
+            alternating_prefixes: Prefixes::Owned(&self.alternating_prefixes),
+            attribution: self.attribution.as_deref(),
+            attribution_link: self.attribution_link.as_deref(),
+            collapsible: self.collapsible.as_deref(),
+            ellipsis_line_marker: self.ellipsis_line_marker.as_deref(),
+            empty_line_fill: &self.empty_line_fill,
+            fenced: self.fenced.as_deref(),
+            filter_lines_starting_with: self.filter_lines_starting_with.as_deref(),
+            first_line_prefix: self.first_line_prefix.as_deref(),
+            header: self.header.as_deref(),
+            indent: &self.indent,
+            plain_indent: &self.plain_indent,
+            source: Source::Text(&self.text),
+            timestamp: self.timestamp.as_deref(),
+            truncation_suffix: &self.truncation_suffix,
+            whitespace_chars: &self.whitespace_chars,
+            wrap_continuation: self.wrap_continuation.as_deref(),
+            ..self.config
+        }
+    }
+
+    /// Text being quoted.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// See [`Blockquote::map_lines`].
+    pub fn map_lines<F, S>(&self, f: F) -> Self
+    where
+        F: FnMut(&str) -> S,
+        S: AsRef<str>,
+    {
+        self.as_blockquote().map_lines(f)
+    }
+
+    /// See [`Blockquote::first_line`].
+    pub fn first_line(&self) -> Blockquote<'_> {
+        self.as_blockquote().first_line()
+    }
+
+    /// See [`Blockquote::prepare`].
+    pub fn prepare(&self) -> PreparedBlockquote<'_> {
+        self.as_blockquote().prepare()
+    }
+
+    /// Replace the text being quoted, keeping the configuration.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+
+        self
+    }
+
+    /// See [`Blockquote::alert`].
+    pub fn alert(mut self, alert: AlertKind<'_>) -> Self {
+        if let AlertKind::Custom(kind) = alert {
+            self.alert = kind.to_string();
+        }
+
+        self.config.alert = Some(owned_alert(alert));
+
+        self
+    }
+
+    /// See [`Blockquote::alternating_prefixes`].
+    pub fn alternating_prefixes(mut self, alternating_prefixes: &[&str]) -> Self {
+        self.alternating_prefixes = alternating_prefixes
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        self
+    }
+
+    /// See [`Blockquote::attribution`].
+    pub fn attribution(mut self, author: impl Into<String>) -> Self {
+        self.attribution = Some(author.into());
+        self.attribution_link = None;
+
+        self
+    }
+
+    /// See [`Blockquote::attribution_link`].
+    pub fn attribution_link(mut self, author: impl Into<String>, url: impl Into<String>) -> Self {
+        self.attribution = Some(author.into());
+        self.attribution_link = Some(url.into());
+
+        self
+    }
+
+    /// See [`Blockquote::empty_line_fill`].
+    pub fn empty_line_fill(mut self, empty_line_fill: impl Into<String>) -> Self {
+        self.empty_line_fill = empty_line_fill.into();
+
+        self
+    }
+
+    /// See [`Blockquote::fenced`].
+    pub fn fenced(mut self, language: Option<&str>) -> Self {
+        self.fenced = Some(language.unwrap_or_default().to_string());
+
+        self
+    }
+
+    /// See [`Blockquote::first_line_prefix`].
+    pub fn first_line_prefix(mut self, first_line_prefix: impl Into<String>) -> Self {
+        self.first_line_prefix = Some(first_line_prefix.into());
+
+        self
+    }
+
+    /// See [`Blockquote::wrap_continuation`].
+    pub fn wrap_continuation(mut self, wrap_continuation: impl Into<String>) -> Self {
+        self.wrap_continuation = Some(wrap_continuation.into());
+
+        self
+    }
+
+    /// See [`Blockquote::header`].
+    pub fn header(mut self, header: impl Into<String>) -> Self {
+        self.header = Some(header.into());
+
+        self
+    }
+
+    /// See [`Blockquote::collapsible`].
+    pub fn collapsible(mut self, summary: impl Into<String>) -> Self {
+        self.collapsible = Some(summary.into());
+
+        self
+    }
+
+    /// See [`Blockquote::indent`].
+    pub fn indent(mut self, indent: impl Into<String>) -> Self {
+        self.indent = indent.into();
+
+        self
+    }
+
+    /// See [`Blockquote::ellipsis_line_marker`].
+    pub fn ellipsis_line_marker(mut self, marker: impl Into<String>) -> Self {
+        self.ellipsis_line_marker = Some(marker.into());
+
+        self
+    }
+
+    /// See [`Blockquote::filter_lines_starting_with`].
+    pub fn filter_lines_starting_with(mut self, prefix: impl Into<String>) -> Self {
+        self.filter_lines_starting_with = Some(prefix.into());
+
+        self
+    }
+
+    /// See [`Blockquote::plain_indent`].
+    pub fn plain_indent(mut self, plain_indent: impl Into<String>) -> Self {
+        self.plain_indent = plain_indent.into();
+
+        self
+    }
+
+    /// See [`Blockquote::timestamp`].
+    pub fn timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = Some(timestamp.into());
+
+        self
+    }
+
+    /// See [`Blockquote::truncation_suffix`].
+    pub fn truncation_suffix(mut self, truncation_suffix: impl Into<String>) -> Self {
+        self.truncation_suffix = truncation_suffix.into();
+
+        self
+    }
+
+    /// See [`Blockquote::whitespace_chars`].
+    pub fn whitespace_chars(mut self, whitespace_chars: impl Into<String>) -> Self {
+        self.whitespace_chars = whitespace_chars.into();
+
+        self
+    }
+
+    /// See [`Blockquote::try_hard_limit`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LimitOverflow`] if the soft and hard limits add up to more
+    /// than [`usize::MAX`].
+    pub fn try_hard_limit(mut self, hard_limit: usize) -> Result<Self, LimitOverflow> {
+        self.config = self.config.try_hard_limit(hard_limit)?;
+
+        Ok(self)
+    }
+
+    /// See [`Blockquote::soft_limit_ratio`].
+    pub fn soft_limit_ratio(mut self, ratio: f32) -> Self {
+        let soft_limit = self.as_blockquote().soft_limit_ratio(ratio).soft_limit;
+        self.config = self.config.soft_limit(soft_limit);
+
+        self
+    }
+
+    delegate! {
+        soft_limit(soft_limit: usize),
+        hard_limit(hard_limit: usize),
+        hard_limit_only(hard_limit_only: usize),
+        headings(headings: HeadingPolicy),
+        images(images: ImagePolicy),
+        backticks(backticks: BacktickPolicy),
+        balance_emphasis(balance_emphasis: bool),
+        bidi_isolate(bidi_isolate: bool),
+        break_on(boundary: Boundary),
+        close_code_fences(close_code_fences: bool),
+        dedent(dedent: bool),
+        diff_mode(diff_mode: bool),
+        escape_existing_quotes(escape_existing_quotes: bool),
+        escape(profile: EscapeProfile),
+        escape_markdown(escape_markdown: bool),
+        expand_tabs(tab_size: usize),
+        flatten_nested(flatten_nested: bool),
+        inline_markdown_aware(inline_markdown_aware: bool),
+        keep_trailing_blank_lines(keep_trailing_blank_lines: bool),
+        lazy(lazy: bool),
+        nest_existing(nest_existing: bool),
+        nesting_style(nesting_style: NestingStyle),
+        normalize_alert_case(normalize_alert_case: bool),
+        per_line_limit(per_line_limit: usize),
+        prefix_mode(prefix_mode: PrefixMode),
+        preserve_hard_breaks(preserve_hard_breaks: bool),
+        html_line_breaks(html_line_breaks: bool),
+        wrap(width: usize),
+        limit_unit(limit_unit: LimitUnit),
+        max_bytes(max_bytes: usize),
+        min_chars(min_chars: usize),
+        min_chars_policy(policy: MinCharsPolicy),
+        leading_newline(leading_newline: bool),
+        line_numbers(start: usize),
+        timestamp_every_line(timestamp_every_line: bool),
+        trailing_newline(trailing_newline: bool),
+        trim_leading_blank_lines(trim_leading_blank_lines: bool),
+        unicode_newlines(unicode_newlines: bool),
+        line_ending(line_ending: LineEnding),
+        render(format: Format),
+        rst_indent(rst_indent: usize),
+        ellipsis_on_new_line(ellipsis_on_new_line: bool),
+        ellipsis_replaces(ellipsis_replaces: bool),
+        with_ellipsis(with_ellipsis: bool),
+        sanitize_bidi(policy: BidiPolicy),
+        control_chars(policy: ControlPolicy),
+        squash_whitespace(squash_whitespace: bool),
+        strikethrough(strikethrough: bool),
+        strip_ansi(strip_ansi: bool),
+        strip_zero_width(strip_zero_width: bool),
+        strip_front_matter(strip_front_matter: bool),
+        zero_width_is_empty(zero_width_is_empty: bool),
+    }
+
+    /// See [`Blockquote::strip_markdown`].
+    #[cfg(feature = "strip-markdown")]
+    pub fn strip_markdown(mut self, strip_markdown: bool) -> Self {
+        self.config = self.config.strip_markdown(strip_markdown);
+
+        self
+    }
+
+    /// See [`Blockquote::is_empty`].
+    pub fn is_empty(&self) -> bool {
+        self.as_blockquote().is_empty()
+    }
+}
+
+impl Display for BlockquoteBuf {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        self.as_blockquote().fmt(f)
+    }
+}
+
+/// Alert kind without its borrowed custom kind, which is stored separately.
+const fn owned_alert(alert: AlertKind<'_>) -> AlertKind<'static> {
+    match alert {
+        AlertKind::Caution => AlertKind::Caution,
+        AlertKind::Important => AlertKind::Important,
+        AlertKind::Note => AlertKind::Note,
+        AlertKind::Tip => AlertKind::Tip,
+        AlertKind::Warning => AlertKind::Warning,
+        AlertKind::Custom(_) => AlertKind::Custom(""),
+    }
+}
+
+/// Quote lines yielded by an iterator, each formatted as its own quoted line.
+///
+/// The lines are collected and joined by newlines, exactly like
+/// [`Blockquote::from_lines`], which quotes lines without allocating.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_blockquote_formatter::BlockquoteBuf;
+///
+/// let logs = ["started", "", "stopped"];
+/// let blockquote = logs.iter().copied().collect::<BlockquoteBuf>();
+///
+/// assert_eq!(blockquote.to_string(), "> started\n> \n> stopped");
+/// ```
+impl<'a> FromIterator<&'a str> for BlockquoteBuf {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(lines: I) -> Self {
+        let mut text = String::new();
+
+        for (index, line) in lines.into_iter().enumerate() {
+            if index > 0 {
+                text.push('\n');
+            }
+
+            text.push_str(line);
+        }
+
+        Self::new(text)
+    }
+}
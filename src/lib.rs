@@ -1,263 +1,9810 @@
 #![doc = include_str!("../README.md")]
 #![no_std]
 
-#[cfg(test)]
+#[cfg(any(feature = "alloc", test))]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+mod buf;
+mod source;
+#[cfg(feature = "strip-markdown")]
+mod strip;
+
+#[cfg(feature = "alloc")]
+pub use self::buf::BlockquoteBuf;
+
+use self::source::Source;
+use core::{
+    fmt::{Display, Error as FmtError, Formatter, Write},
+    hash::{Hash, Hasher},
+    iter, mem, str,
+};
+
+/// Maximum number of nested emphasis runs tracked to be balanced.
+const MAX_EMPHASIS_DEPTH: usize = 8;
+
+/// String replacing images without alt text.
+const IMAGE_PLACEHOLDER: &str = "[image]";
+
+/// Character replacing backticks, the modifier letter grave accent.
+const GRAVE_LOOKALIKE: char = '\u{2CB}';
+
+/// Character for a carriage return.
+const CARRIAGE_RETURN: char = '\r';
+
+/// Length of the escape of an ASCII control character, such as `\x07`.
+const CONTROL_LEN: usize = "\\x07".len();
+
+/// Character for an escape, starting ANSI escape sequences.
+const ESCAPE: char = '\x1B';
+
+/// Character for a newline.
+const NEWLINE: char = '\n';
+
+/// Character for the first strong isolate, opening a bidirectional isolate.
+const FIRST_STRONG_ISOLATE: char = '\u{2068}';
+
+/// Character for the left-to-right embedding, the first of the embedding and
+/// override controls.
+const LEFT_TO_RIGHT_EMBEDDING: char = '\u{202A}';
+
+/// Character for the left-to-right isolate, the first of the isolate controls.
+const LEFT_TO_RIGHT_ISOLATE: char = '\u{2066}';
+
+/// Character for the pop directional formatting, closing an embedding or
+/// override.
+const POP_DIRECTIONAL_FORMATTING: char = '\u{202C}';
+
+/// Character for the pop directional isolate, closing a bidirectional isolate.
+const POP_DIRECTIONAL_ISOLATE: char = '\u{2069}';
+
+/// Character for the right-to-left override, the last of the embedding and
+/// override controls.
+const RIGHT_TO_LEFT_OVERRIDE: char = '\u{202E}';
+
+/// String for opening and closing strikethrough.
+const STRIKETHROUGH: &str = "~~";
+
+/// Character for a tab.
+const TAB: char = '\t';
+
+/// Number of columns between the tab stops of markdown.
+const TAB_STOP: usize = 4;
+
+/// Character for the zero-width joiner, joining emoji into a single one.
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+/// Error returned when writing a formatted blockquote fails.
+///
+/// Returned by [`Blockquote::write_bytes`] and [`Blockquote::write_to`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlockquoteError {
+    /// Buffer is too small to hold the formatted blockquote.
+    Capacity(CapacityError),
+    /// Writer failed to write the formatted blockquote.
+    Fmt(FmtError),
+}
+
+impl Display for BlockquoteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        match self {
+            Self::Capacity(source) => Display::fmt(source, f),
+            Self::Fmt(_) => f.write_str("writer failed to write the blockquote"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BlockquoteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Capacity(source) => Some(source),
+            Self::Fmt(source) => Some(source),
+        }
+    }
+}
+
+impl From<CapacityError> for BlockquoteError {
+    fn from(source: CapacityError) -> Self {
+        Self::Capacity(source)
+    }
+}
+
+impl From<FmtError> for BlockquoteError {
+    fn from(source: FmtError) -> Self {
+        Self::Fmt(source)
+    }
+}
+
+/// Error returned when a formatted blockquote doesn't fit in a buffer.
+///
+/// Contained in a [`BlockquoteError`] returned by [`Blockquote::write_bytes`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct CapacityError {
+    written: usize,
+}
+
+impl CapacityError {
+    /// Number of bytes written to the buffer before running out of capacity.
+    ///
+    /// The bytes written are always valid UTF-8.
+    pub const fn written(&self) -> usize {
+        self.written
+    }
+}
+
+impl Display for CapacityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        f.write_str("buffer is too small to hold the blockquote")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+/// Error returned when the soft and hard limits add up to more than
+/// [`usize::MAX`].
+///
+/// Returned by [`Blockquote::try_hard_limit`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LimitOverflow;
+
+impl Display for LimitOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        f.write_str("soft and hard limits overflow when added")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LimitOverflow {}
+
+/// Line break written between lines of a formatted blockquote.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LineEnding {
+    /// Carriage return followed by a line feed (`\r\n`).
+    CrLf,
+    /// Line feed (`\n`).
+    Lf,
+}
+
+impl LineEnding {
+    /// String of the line ending.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CrLf => "\r\n",
+            Self::Lf => "\n",
+        }
+    }
+}
+
+/// Boundary of markdown that truncation backs off to.
+///
+/// Set with [`Blockquote::break_on`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Boundary {
+    /// End of a list item, marked with `-`, `*`, or `+`, or numbered like `1.`
+    /// or `1)`.
+    ListItem,
+}
+
+/// Unit the soft and hard limits are measured in.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum LimitUnit {
+    /// Limits are measured in UTF-8 bytes.
+    ///
+    /// Truncation never splits a character, so a character is only formatted
+    /// if all of its bytes fit within the limit.
+    Bytes,
+    /// Limits are measured in Unicode scalar values.
+    Chars,
+    /// Limits are measured in monospace terminal columns, as determined by
+    /// `unicode-width`.
+    ///
+    /// Zero-width characters, such as combining marks, don't count. Requires
+    /// the `unicode-width` feature.
+    #[cfg(feature = "unicode-width")]
+    Columns,
+    /// Limits are measured in extended grapheme clusters, as determined by
+    /// `unicode-segmentation`.
+    ///
+    /// Characters continuing a cluster, such as combining marks and the rest
+    /// of an emoji sequence, don't count. Requires the `unicode-segmentation`
+    /// feature.
+    #[cfg(feature = "unicode-segmentation")]
+    Graphemes,
+}
+
+/// What's formatted when fewer characters than the minimum would be formatted
+/// before the text is cut off.
+///
+/// Set with [`Blockquote::min_chars_policy`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MinCharsPolicy {
+    /// Nothing is formatted, as if the blockquote were empty.
+    Empty,
+    /// The whole text is formatted, ignoring the soft and hard limits and the
+    /// maximum bytes.
+    Unlimited,
+}
+
+/// How the quote markers of nested lines are joined.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NestingStyle {
+    /// Markers are joined without spaces, such as `>>> `.
+    Contiguous,
+    /// Markers are separated by spaces, such as `> > > `.
+    Spaced,
+}
+
+/// Format a blockquote is rendered in.
+///
+/// Set with [`Blockquote::render`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Format {
+    /// An AsciiDoc quote block, with lines delimited by `____` lines.
+    ///
+    /// The block starts with a `[quote]` attribute line, which includes the
+    /// [attribution], without its link, rather than formatting it after the
+    /// text. Lines aren't prefixed, and lines starting with `____` are
+    /// escaped with `{empty}` so the text can't close the block early.
+    ///
+    /// [attribution]: Blockquote::attribution
+    AsciiDoc,
+    /// A Discord multi-line quote, starting with a single `>>> ` marker that
+    /// quotes everything after it.
+    ///
+    /// Lines aren't prefixed. The [attribution] isn't formatted, as it would
+    /// be quoted too.
+    ///
+    /// [attribution]: Blockquote::attribution
+    DiscordMultiline,
+    /// An HTML `<blockquote>` element.
+    ///
+    /// Lines are separated by `<br>` and paragraphs are wrapped in `<p>`
+    /// elements, and `<`, `>`, `&`, and the ellipsis are escaped. Markdown
+    /// written by other options, such as the header, is written as text.
+    Html,
+    /// A Jira or Confluence quote, with lines between `{quote}` macros.
+    ///
+    /// Lines aren't prefixed, and braces are escaped so the text can't close
+    /// the quote early.
+    Jira,
+    /// A markdown blockquote, with lines prefixed by quote markers.
+    Markdown,
+    /// An Org quote block, with lines between `#+BEGIN_QUOTE` and
+    /// `#+END_QUOTE` lines.
+    ///
+    /// Lines aren't prefixed, and lines starting with `#+END_QUOTE` are
+    /// escaped with a comma so the text can't close the block early.
+    Org,
+    /// Plain text, with lines indented by the [plain indentation].
+    ///
+    /// Blank lines aren't indented.
+    ///
+    /// [plain indentation]: Blockquote::plain_indent
+    Plain,
+    /// A Textile block quote, starting with a `bq. ` signature.
+    ///
+    /// Quotes of more than one paragraph are extended blocks, starting with
+    /// `bq.. ` and ending with a blank line and a `p.` signature starting
+    /// the following paragraph. Lines aren't prefixed.
+    Textile,
+    /// A reStructuredText block quote, with lines indented by the [reST
+    /// indentation].
+    ///
+    /// Blank lines aren't indented.
+    ///
+    /// [reST indentation]: Blockquote::rst_indent
+    Rst,
+}
+
+/// How headings in the text are formatted.
+///
+/// Set with [`Blockquote::headings`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HeadingPolicy {
+    /// Headings are lowered by a number of levels, down to the sixth level.
+    ///
+    /// Setext headings, underlined with `=` or `-`, are rewritten as ATX
+    /// headings starting with `#`.
+    Demote(usize),
+    /// Headings are kept as-is.
+    Keep,
+    /// Headings are formatted as plain text, without their `#` markers or
+    /// underlines.
+    Strip,
+}
+
+/// How backticks in the text are formatted.
+///
+/// Set with [`Blockquote::backticks`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BacktickPolicy {
+    /// Backticks are escaped with a backslash, such as `` \` ``.
+    Escape,
+    /// Backticks are kept as-is.
+    Keep,
+    /// Backticks are replaced by the visually similar modifier letter grave
+    /// accent, `ˋ` (U+02CB).
+    Replace,
+}
+
+/// How bidirectional control characters in the text are sanitized.
+///
+/// Set with [`Blockquote::sanitize_bidi`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BidiPolicy {
+    /// Embeddings, overrides, and isolates left open at the end of a line are
+    /// closed with pop directional formatting (U+202C) and pop directional
+    /// isolate (U+2069) characters.
+    Close,
+    /// Controls are replaced by visible escapes of their code points, such as
+    /// `\u{202E}`.
+    Escape,
+    /// Controls are kept as-is.
+    Keep,
+    /// Controls are removed.
+    Strip,
+}
+
+/// How ASCII control characters in the text are formatted.
+///
+/// Newlines, carriage returns, and tabs aren't affected, as they're handled
+/// separately. Set with [`Blockquote::control_chars`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ControlPolicy {
+    /// Controls are replaced by visible escapes of their values, such as
+    /// `\x07`.
+    Escape,
+    /// Controls are kept as-is.
+    Keep,
+    /// Controls are removed.
+    Strip,
+}
+
+/// Rules for escaping punctuation in the text.
+///
+/// Set with [`Blockquote::escape`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EscapeProfile {
+    /// Punctuation with meaning in markdown, ``\ ` * _ [ ] ( ) # + - . ! > |
+    /// ~``, is escaped.
+    Markdown,
+    /// Every character reserved by Telegram's MarkdownV2 parse mode,
+    /// ``\ _ * [ ] ( ) ~ ` > # + - = | { } . !``, is escaped.
+    ///
+    /// Telegram rejects messages with reserved characters that aren't
+    /// escaped. The quote markers of the blockquote aren't escaped, so it's
+    /// still formatted as a quote.
+    TelegramMarkdownV2,
+}
+
+impl EscapeProfile {
+    /// Whether a character is escaped.
+    fn escapes(self, character: char) -> bool {
+        match self {
+            Self::Markdown => "\\`*_[]()#+-.!>|~".contains(character),
+            Self::TelegramMarkdownV2 => "\\_*[]()~`>#+-=|{}.!".contains(character),
+        }
+    }
+}
+
+/// How images in the text are formatted.
+///
+/// Set with [`Blockquote::images`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ImagePolicy {
+    /// Images are replaced by their alt text, or `[image]` if it's empty.
+    AltText,
+    /// Images are kept as-is.
+    Keep,
+    /// Images are removed, along with the whitespace following them.
+    Remove,
+}
+
+/// Which lines are prefixed with quote markers.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PrefixMode {
+    /// Every line is prefixed.
+    EveryLine,
+    /// Only the first line is prefixed, and the rest lazily continue it.
+    FirstOnly,
+}
+
+/// Kind of an alert, a blockquote rendered as a callout.
+///
+/// Set with [`Blockquote::alert`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AlertKind<'a> {
+    /// Critical content warning of negative outcomes.
+    Caution,
+    /// Crucial information necessary to achieve goals.
+    Important,
+    /// Useful information to be aware of.
+    Note,
+    /// Helpful advice.
+    Tip,
+    /// Urgent information needing immediate attention.
+    Warning,
+    /// Kind supported by other renderers, such as `"DANGER"`.
+    Custom(&'a str),
+}
+
+impl<'a> AlertKind<'a> {
+    /// Name of the kind, as written in the alert marker.
+    pub const fn as_str(self) -> &'a str {
+        match self {
+            Self::Caution => "CAUTION",
+            Self::Important => "IMPORTANT",
+            Self::Note => "NOTE",
+            Self::Tip => "TIP",
+            Self::Warning => "WARNING",
+            Self::Custom(kind) => kind,
+        }
+    }
+}
+
+/// Quote some text in a markdown blockquote.
+///
+/// Equality and hashing compare the text and configuration rather than the
+/// formatted blockquote, so blockquotes configured differently are unequal
+/// even if they format identically. Compare the formatted strings to
+/// deduplicate by output.
+///
+/// # Examples
+///
+/// Quote the text "hey, this is cool!":
+///
+/// ```
+/// use markdown_blockquote_formatter::Blockquote;
+///
+/// let blockquote = Blockquote::new("hey, this is cool!");
+///
+/// assert_eq!(blockquote.to_string(), "> hey, this is cool!");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Blockquote<'a> {
+    alert: Option<AlertKind<'a>>,
+    alternating_prefixes: Prefixes<'a>,
+    attribution: Option<&'a str>,
+    attribution_link: Option<&'a str>,
+    backticks: BacktickPolicy,
+    balance_emphasis: bool,
+    bidi_isolate: bool,
+    break_on: Option<Boundary>,
+    close_code_fences: bool,
+    collapsible: Option<&'a str>,
+    control_chars: ControlPolicy,
+    /// Position of the source to cut the text at, before any limit.
+    cut: Option<usize>,
+    dedent: bool,
+    diff_mode: bool,
+    ellipsis_line_marker: Option<&'a str>,
+    ellipsis_on_new_line: bool,
+    ellipsis_replaces: bool,
+    empty_line_fill: &'a str,
+    escape: Option<EscapeProfile>,
+    escape_existing_quotes: bool,
+    /// Language of the code block wrapping the text, if any, which may be
+    /// empty.
+    fenced: Option<&'a str>,
+    filter_lines_starting_with: Option<&'a str>,
+    first_line_prefix: Option<&'a str>,
+    flatten_nested: bool,
+    hard_limit: Option<usize>,
+    hard_limit_only: Option<usize>,
+    header: Option<&'a str>,
+    headings: HeadingPolicy,
+    html_line_breaks: bool,
+    images: ImagePolicy,
+    indent: &'a str,
+    inline_markdown_aware: bool,
+    keep_trailing_blank_lines: bool,
+    lazy: bool,
+    leading_newline: bool,
+    limit_unit: LimitUnit,
+    line_ending: LineEnding,
+    line_numbers: Option<usize>,
+    max_bytes: Option<usize>,
+    min_chars: Option<usize>,
+    min_chars_policy: MinCharsPolicy,
+    nest_existing: bool,
+    nesting_style: NestingStyle,
+    normalize_alert_case: bool,
+    per_line_limit: Option<usize>,
+    plain_indent: &'a str,
+    prefix_mode: PrefixMode,
+    preserve_hard_breaks: bool,
+    render: Format,
+    rst_indent: usize,
+    sanitize_bidi: BidiPolicy,
+    soft_limit: usize,
+    source: Source<'a>,
+    squash_whitespace: bool,
+    strikethrough: bool,
+    strip_ansi: bool,
+    strip_front_matter: bool,
+    #[cfg(feature = "strip-markdown")]
+    strip_markdown: bool,
+    strip_zero_width: bool,
+    tab_size: Option<usize>,
+    timestamp: Option<&'a str>,
+    timestamp_every_line: bool,
+    trailing_newline: bool,
+    trim_leading_blank_lines: bool,
+    truncation_suffix: &'a str,
+    unicode_newlines: bool,
+    whitespace_chars: &'a str,
+    with_ellipsis: bool,
+    wrap: Option<usize>,
+    wrap_continuation: Option<&'a str>,
+    zero_width_is_empty: bool,
+}
+
+impl<'a> Blockquote<'a> {
+    /// Ellipsis appended to text that's cut off, unless another [truncation
+    /// suffix] is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!").soft_limit(9);
+    ///
+    /// assert!(blockquote.to_string().ends_with(Blockquote::DEFAULT_ELLIPSIS));
+    /// ```
+    ///
+    /// [truncation suffix]: Self::truncation_suffix
+    pub const DEFAULT_ELLIPSIS: &'static str = "…";
+
+    /// Prefix starting each quoted line, nested once for each level of quotes
+    /// the line is nested within.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!");
+    ///
+    /// assert!(blockquote.to_string().starts_with(Blockquote::DEFAULT_PREFIX));
+    /// ```
+    pub const DEFAULT_PREFIX: &'static str = "> ";
+
+    /// Create a new markdown blockquote formatter.
+    pub const fn new(text: &'a str) -> Self {
+        Self::from_source(Source::Text(text))
+    }
+
+    /// Create a new markdown blockquote formatter for text already split into
+    /// lines.
+    ///
+    /// Each line is formatted as its own quoted line, exactly as if the lines
+    /// were joined by newlines: limits apply across all of the lines, and
+    /// empty lines are blank lines. Lines containing newlines are split into
+    /// multiple lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::from_lines(&["first", "", "second"]);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> first\n> \n> second");
+    /// ```
+    pub const fn from_lines(lines: &'a [&'a str]) -> Self {
+        Self::from_source(Source::Lines(lines))
+    }
+
+    const fn from_source(source: Source<'a>) -> Self {
+        Self {
+            alert: None,
+            alternating_prefixes: Prefixes::Borrowed(&[]),
+            attribution: None,
+            attribution_link: None,
+            backticks: BacktickPolicy::Keep,
+            balance_emphasis: false,
+            bidi_isolate: false,
+            break_on: None,
+            close_code_fences: false,
+            collapsible: None,
+            control_chars: ControlPolicy::Keep,
+            cut: None,
+            dedent: false,
+            diff_mode: false,
+            ellipsis_line_marker: None,
+            ellipsis_on_new_line: false,
+            ellipsis_replaces: false,
+            empty_line_fill: "",
+            escape: None,
+            escape_existing_quotes: false,
+            fenced: None,
+            filter_lines_starting_with: None,
+            first_line_prefix: None,
+            flatten_nested: false,
+            hard_limit: None,
+            hard_limit_only: None,
+            header: None,
+            headings: HeadingPolicy::Keep,
+            html_line_breaks: false,
+            images: ImagePolicy::Keep,
+            indent: "",
+            inline_markdown_aware: false,
+            keep_trailing_blank_lines: false,
+            lazy: false,
+            leading_newline: false,
+            limit_unit: LimitUnit::Chars,
+            line_ending: LineEnding::Lf,
+            line_numbers: None,
+            max_bytes: None,
+            min_chars: None,
+            min_chars_policy: MinCharsPolicy::Empty,
+            nest_existing: false,
+            nesting_style: NestingStyle::Spaced,
+            normalize_alert_case: false,
+            per_line_limit: None,
+            plain_indent: "    ",
+            prefix_mode: PrefixMode::EveryLine,
+            preserve_hard_breaks: false,
+            render: Format::Markdown,
+            rst_indent: 4,
+            sanitize_bidi: BidiPolicy::Keep,
+            soft_limit: usize::MAX,
+            source,
+            squash_whitespace: false,
+            strikethrough: false,
+            strip_ansi: false,
+            strip_front_matter: false,
+            #[cfg(feature = "strip-markdown")]
+            strip_markdown: false,
+            strip_zero_width: false,
+            tab_size: None,
+            timestamp: None,
+            timestamp_every_line: false,
+            trailing_newline: false,
+            trim_leading_blank_lines: true,
+            truncation_suffix: Self::DEFAULT_ELLIPSIS,
+            unicode_newlines: false,
+            whitespace_chars: "",
+            with_ellipsis: true,
+            wrap: None,
+            wrap_continuation: None,
+            zero_width_is_empty: false,
+        }
+    }
+
+    /// There is no soft limit in practice by default.
+    pub const fn soft_limit(mut self, soft_limit: usize) -> Self {
+        self.soft_limit = soft_limit;
+
+        self
+    }
+
+    /// Number the lines of the text, starting from a line number, such as for
+    /// quoting code.
+    ///
+    /// Each line is prefixed by its number after the quote marker, followed
+    /// by ` | `. Lines are numbered by their position in the text, so lines
+    /// that aren't formatted, such as skipped leading blank lines and
+    /// [filtered out] lines, still take up their numbers. Numbers are
+    /// right-aligned to the width of the largest formatted, and blank lines
+    /// are numbered too. Lines continued by a [wrap] have a blank gutter. The
+    /// gutter counts toward the wrap width and the [maximum bytes], but not
+    /// toward the soft and hard limits.
+    ///
+    /// Lines aren't numbered by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("let x = 5;\n\nx").line_numbers(9);
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     ">  9 | let x = 5;\n> 10 | \n> 11 | x",
+    /// );
+    /// ```
+    ///
+    /// [filtered out]: Self::filter_lines_starting_with
+    /// [maximum bytes]: Self::max_bytes
+    /// [wrap]: Self::wrap
+    pub const fn line_numbers(mut self, start: usize) -> Self {
+        self.line_numbers = Some(start);
+
+        self
+    }
+
+    /// Set the soft limit to a fraction of the number of characters in the
+    /// text, such as `0.3` for the first 30% of it.
+    ///
+    /// The ratio is clamped between `0.0` and `1.0`, and the limit is rounded
+    /// down. It's computed from the text when called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!").soft_limit_ratio(0.5);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> hey, this…");
+    /// ```
+    pub fn soft_limit_ratio(self, ratio: f32) -> Self {
+        let chars = self.content().char_count(usize::MAX);
+
+        // Casting saturates, and maps NaN to zero.
+        self.soft_limit((chars as f32 * ratio.clamp(0.0, 1.0)) as usize)
+    }
+
+    /// Set how headings in the text are formatted.
+    ///
+    /// Headings quoted from a message may be out of place in a reply, so they
+    /// can be stripped to plain text or demoted to a lower level. ATX headings
+    /// start with one to six `#` markers, and setext headings are a line
+    /// underlined with `=` or `-`. Setext headings spanning several lines are
+    /// left as is. Demoted markers don't count toward the soft and hard
+    /// limits, and neither do removed markers and underlines.
+    ///
+    /// Headings are kept by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, HeadingPolicy};
+    ///
+    /// let blockquote = Blockquote::new("# Title\nSubtitle\n---");
+    ///
+    /// assert_eq!(
+    ///     blockquote.headings(HeadingPolicy::Strip).to_string(),
+    ///     "> Title\n> Subtitle",
+    /// );
+    /// assert_eq!(
+    ///     blockquote.headings(HeadingPolicy::Demote(2)).to_string(),
+    ///     "> ### Title\n> #### Subtitle",
+    /// );
+    /// ```
+    pub const fn headings(mut self, headings: HeadingPolicy) -> Self {
+        self.headings = headings;
+
+        self
+    }
+
+    /// Set how backticks in the text are formatted.
+    ///
+    /// A quoted message with an odd number of backticks can turn the text
+    /// following the blockquote into a code span, so backticks can be escaped
+    /// or replaced. Backslash escapes don't work inside code spans on all
+    /// renderers, so backticks may be replaced by a lookalike character
+    /// instead. Backslashes and replacements count toward the soft and hard
+    /// limits.
+    ///
+    /// Backticks are kept by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{BacktickPolicy, Blockquote};
+    ///
+    /// let blockquote = Blockquote::new("run `ls");
+    ///
+    /// assert_eq!(
+    ///     blockquote.backticks(BacktickPolicy::Escape).to_string(),
+    ///     r"> run \`ls",
+    /// );
+    /// assert_eq!(
+    ///     blockquote.backticks(BacktickPolicy::Replace).to_string(),
+    ///     "> run ˋls",
+    /// );
+    /// ```
+    pub const fn backticks(mut self, backticks: BacktickPolicy) -> Self {
+        self.backticks = backticks;
+
+        self
+    }
+
+    /// Set how images in the text are formatted.
+    ///
+    /// Images often don't render where quotes are shown, so they can be
+    /// replaced by their alt text or removed. Both inline images, such as
+    /// `![alt](url)`, and reference images, such as `![alt][ref]`, are
+    /// replaced. Alt text counts toward the soft and hard limits, but the rest
+    /// of a replaced image doesn't. When images are removed, lines with only
+    /// images are removed entirely, along with the whitespace around them.
+    ///
+    /// Images are kept by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, ImagePolicy};
+    ///
+    /// let blockquote = Blockquote::new("look at ![a cat](cat.png) here");
+    ///
+    /// assert_eq!(
+    ///     blockquote.images(ImagePolicy::AltText).to_string(),
+    ///     "> look at a cat here",
+    /// );
+    /// assert_eq!(
+    ///     blockquote.images(ImagePolicy::Remove).to_string(),
+    ///     "> look at here",
+    /// );
+    /// ```
+    pub const fn images(mut self, images: ImagePolicy) -> Self {
+        self.images = images;
+
+        self
+    }
+
+    /// Set the hard limit to break off the formatted text.
+    ///
+    /// The hard limit is *in addition to* the soft limit. Providing a value of
+    /// 50 via [`soft_limit`] and 10 via [`hard_limit`] results in a hard limit
+    /// of 60, saturating at [`usize::MAX`]. Without a soft limit the hard
+    /// limit never takes effect; use [`hard_limit_only`] instead.
+    ///
+    /// URLs are never cut by the hard limit: text is cut before a URL that
+    /// doesn't fit, along with the whitespace preceding it.
+    ///
+    /// There is no hard limit by default. To catch limits that saturate, use
+    /// [`try_hard_limit`].
+    ///
+    /// [`hard_limit`]: Self::hard_limit
+    /// [`hard_limit_only`]: Self::hard_limit_only
+    /// [`soft_limit`]: Self::soft_limit
+    /// [`try_hard_limit`]: Self::try_hard_limit
+    pub const fn hard_limit(mut self, hard_limit: usize) -> Self {
+        self.hard_limit = Some(hard_limit);
+
+        self
+    }
+
+    /// Set the [hard limit], unless adding it to the soft limit overflows.
+    ///
+    /// The soft limit must be set first, since there's no soft limit in
+    /// practice by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!").soft_limit(10);
+    ///
+    /// assert_eq!(blockquote.try_hard_limit(2)?.to_string(), "> hey, this is…");
+    /// assert!(blockquote.soft_limit(usize::MAX).try_hard_limit(2).is_err());
+    /// # Ok::<(), markdown_blockquote_formatter::LimitOverflow>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LimitOverflow`] if the soft and hard limits add up to more
+    /// than [`usize::MAX`].
+    ///
+    /// [hard limit]: Self::hard_limit
+    pub const fn try_hard_limit(self, hard_limit: usize) -> Result<Self, LimitOverflow> {
+        match self.soft_limit.checked_add(hard_limit) {
+            Some(_) => Ok(self.hard_limit(hard_limit)),
+            None => Err(LimitOverflow),
+        }
+    }
+
+    /// Set the prefix of the first line, replacing its indentation and quote
+    /// markers.
+    ///
+    /// Subsequent lines use the usual prefix. Like the usual prefix, the first
+    /// line's prefix doesn't count toward the soft and hard limits. The first
+    /// line is the marker of an [alert] or the [header] if either is set.
+    ///
+    /// The first line uses the usual prefix by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hi\nthere").first_line_prefix("Alice ❯ ");
+    ///
+    /// assert_eq!(blockquote.to_string(), "Alice ❯ hi\n> there");
+    /// ```
+    ///
+    /// [alert]: Self::alert
+    /// [header]: Self::header
+    pub const fn first_line_prefix(mut self, first_line_prefix: &'a str) -> Self {
+        self.first_line_prefix = Some(first_line_prefix);
+
+        self
+    }
+
+    /// Set prefixes to cycle through for the lines of the text, replacing
+    /// their indentation and quote markers.
+    ///
+    /// The first line of the text uses the first prefix, the second line the
+    /// second prefix, and so on, starting over after the last prefix. This is
+    /// useful for rendering conversations between several parties. Lines
+    /// continued by wrapping and other lines, such as the [header], use the
+    /// usual prefix. Like the usual prefix, the prefixes don't count toward the
+    /// soft and hard limits.
+    ///
+    /// Lines use the usual prefix by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hi\nhello\nbye")
+    ///     .alternating_prefixes(&["> A: ", "> B: "]);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> A: hi\n> B: hello\n> A: bye");
+    /// ```
+    ///
+    /// [header]: Self::header
+    pub const fn alternating_prefixes(mut self, alternating_prefixes: &'a [&'a str]) -> Self {
+        self.alternating_prefixes = Prefixes::Borrowed(alternating_prefixes);
+
+        self
+    }
+
+    /// Set the maximum number of bytes of the formatted blockquote.
+    ///
+    /// Unlike the soft and hard limits, the maximum covers everything
+    /// formatted, including prefixes, line breaks, and the ellipsis. If the
+    /// formatted blockquote would exceed it, the text is cut as late as
+    /// possible such that it fits, never splitting a character. If not even
+    /// the start of the blockquote fits, nothing is formatted.
+    ///
+    /// There is no maximum by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("héllo wörld").max_bytes(10);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> héll…");
+    /// assert!(blockquote.to_string().len() <= 10);
+    /// ```
+    pub const fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+
+        self
+    }
+
+    /// Set the minimum number of characters of the text formatted before it's
+    /// cut off.
+    ///
+    /// If the limits would cut the text off after fewer characters, only a
+    /// sliver of it would be quoted, so either nothing or the whole text is
+    /// formatted instead, depending on the [policy]. Text that isn't cut off
+    /// is formatted regardless of its length. Characters are counted like
+    /// [`emitted_char_count`].
+    ///
+    /// There is no minimum by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!").min_chars(5);
+    ///
+    /// assert_eq!(blockquote.soft_limit(9).to_string(), "> hey, this…");
+    /// assert_eq!(blockquote.soft_limit(3).to_string(), "");
+    /// ```
+    ///
+    /// [`emitted_char_count`]: Self::emitted_char_count
+    /// [policy]: Self::min_chars_policy
+    pub const fn min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = Some(min_chars);
+
+        self
+    }
+
+    /// Set what's formatted when fewer characters than the [minimum] would be
+    /// formatted.
+    ///
+    /// Nothing is formatted by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, MinCharsPolicy};
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!")
+    ///     .min_chars(5)
+    ///     .min_chars_policy(MinCharsPolicy::Unlimited);
+    ///
+    /// assert_eq!(blockquote.soft_limit(3).to_string(), "> hey, this is cool!");
+    /// ```
+    ///
+    /// [minimum]: Self::min_chars
+    pub const fn min_chars_policy(mut self, policy: MinCharsPolicy) -> Self {
+        self.min_chars_policy = policy;
+
+        self
+    }
+
+    /// Set a header written verbatim as the first quoted line, such as
+    /// `**alice said:**`.
+    ///
+    /// The formatted text follows on the next lines. The header doesn't count
+    /// toward the soft and hard limits, and isn't written if the blockquote is
+    /// [empty]. It follows the marker of an [alert].
+    ///
+    /// There is no header by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!").header("**alice said:**");
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> **alice said:**\n> hey, this is cool!",
+    /// );
+    /// ```
+    ///
+    /// [alert]: Self::alert
+    /// [empty]: Self::is_empty
+    pub const fn header(mut self, header: &'a str) -> Self {
+        self.header = Some(header);
+
+        self
+    }
+
+    /// Wrap the formatted text in a collapsible section with a summary, such
+    /// as `quoted message`.
+    ///
+    /// The section is opened by `<details><summary>` with the summary written
+    /// verbatim, followed by an empty quoted line so that the text is rendered
+    /// as markdown, and closed by `</details>` after the text. Each is on its
+    /// own quoted line. The section doesn't count toward the soft and hard
+    /// limits, and isn't written if the blockquote is [empty]. It follows the
+    /// [header].
+    ///
+    /// The text isn't collapsible by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!").collapsible("alice said");
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> <details><summary>alice said</summary>\n> \n> hey, this is cool!\n> </details>",
+    /// );
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    /// [header]: Self::header
+    pub const fn collapsible(mut self, summary: &'a str) -> Self {
+        self.collapsible = Some(summary);
+
+        self
+    }
+
+    /// Wrap the formatted text in a fenced code block, optionally with the
+    /// language of the code, such as `rust`.
+    ///
+    /// This is useful for quoting code and logs. The fence is longer than any
+    /// run of backticks in the text, so code fences in the text are quoted as
+    /// code. The lines of the fences don't count toward the soft and hard
+    /// limits, but do toward the [maximum bytes]. The code block is closed
+    /// even if the text is truncated, with the ellipsis on the following
+    /// line.
+    ///
+    /// The text isn't wrapped in a code block by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("let x = 1;\nlet y = 2;").fenced(Some("rust"));
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> ```rust\n> let x = 1;\n> let y = 2;\n> ```",
+    /// );
+    /// assert_eq!(
+    ///     blockquote.soft_limit(10).to_string(),
+    ///     "> ```rust\n> let x = 1;\n> ```\n> …",
+    /// );
+    /// ```
+    ///
+    /// [maximum bytes]: Self::max_bytes
+    pub const fn fenced(mut self, language: Option<&'a str>) -> Self {
+        self.fenced = match language {
+            Some(language) => Some(language),
+            None => Some(""),
+        };
+
+        self
+    }
+
+    /// Set an absolute limit to break off the formatted text, independent of
+    /// the soft limit.
+    ///
+    /// The text is cut as soon as the limit is reached, even in the middle of
+    /// a word. When combined with a soft limit, whichever limit is reached
+    /// first applies.
+    ///
+    /// There is no absolute limit by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!").hard_limit_only(7);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> hey, th…");
+    /// ```
+    pub const fn hard_limit_only(mut self, hard_limit_only: usize) -> Self {
+        self.hard_limit_only = Some(hard_limit_only);
+
+        self
+    }
+
+    /// Set the kind of alert to format the blockquote as.
+    ///
+    /// An alert marker such as `[!WARNING]` is written as the first quoted
+    /// line, followed by the formatted text. The marker doesn't count toward
+    /// the soft and hard limits, and isn't written if the blockquote is
+    /// [empty].
+    ///
+    /// Blockquotes aren't alerts by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{AlertKind, Blockquote};
+    ///
+    /// let blockquote = Blockquote::new("mind the gap").alert(AlertKind::Warning);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> [!WARNING]\n> mind the gap");
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    pub const fn alert(mut self, alert: AlertKind<'a>) -> Self {
+        self.alert = Some(alert);
+
+        self
+    }
+
+    /// Whether the kinds of alert markers are written in uppercase, as GitHub
+    /// only recognizes uppercase markers such as `[!NOTE]`.
+    ///
+    /// This applies to [custom alert kinds] and to an alert marker such as
+    /// `[!note]` on the first line of the text, which is written as
+    /// `[!NOTE]`.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{AlertKind, Blockquote};
+    ///
+    /// let blockquote = Blockquote::new("[!note]\nmind the gap").normalize_alert_case(true);
+    /// assert_eq!(blockquote.to_string(), "> [!NOTE]\n> mind the gap");
+    ///
+    /// let blockquote = Blockquote::new("mind the gap")
+    ///     .alert(AlertKind::Custom("danger"))
+    ///     .normalize_alert_case(true);
+    /// assert_eq!(blockquote.to_string(), "> [!DANGER]\n> mind the gap");
+    /// ```
+    ///
+    /// [custom alert kinds]: AlertKind::Custom
+    pub const fn normalize_alert_case(mut self, normalize_alert_case: bool) -> Self {
+        self.normalize_alert_case = normalize_alert_case;
+
+        self
+    }
+
+    /// Set the author to attribute the quote to.
+    ///
+    /// A blank quoted line followed by `> — Author` is written after the
+    /// formatted text, even if the text is truncated. The attribution doesn't
+    /// count toward the soft and hard limits, and isn't written if the
+    /// blockquote is [empty].
+    ///
+    /// There is no attribution by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hello there").attribution("Obi-Wan");
+    ///
+    /// assert_eq!(blockquote.to_string(), "> hello there\n> \n> — Obi-Wan");
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    pub const fn attribution(mut self, author: &'a str) -> Self {
+        self.attribution = Some(author);
+        self.attribution_link = None;
+
+        self
+    }
+
+    /// Set the author to attribute the quote to, linking to the source of the
+    /// quote.
+    ///
+    /// Formatted like [`attribution`], with the author written as a markdown
+    /// link: `> — [Author](url)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote =
+    ///     Blockquote::new("hello there").attribution_link("Obi-Wan", "https://example.com");
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> hello there\n> \n> — [Obi-Wan](https://example.com)",
+    /// );
+    /// ```
+    ///
+    /// [`attribution`]: Self::attribution
+    pub const fn attribution_link(mut self, author: &'a str, url: &'a str) -> Self {
+        self.attribution = Some(author);
+        self.attribution_link = Some(url);
+
+        self
+    }
+
+    /// Whether to close emphasis left open by truncation.
+    ///
+    /// Runs of `*` and `_` opening emphasis or strong emphasis within the
+    /// paragraph being truncated are closed before the ellipsis, so the rest
+    /// of the document isn't emphasized. Markers escaped with a backslash,
+    /// underscores within words, and markers in code spans and fenced code
+    /// blocks aren't counted. The closing markers don't count toward the soft
+    /// and hard limits. Emphasis can't be closed after whitespace, so text cut
+    /// off after whitespace is cut before it instead.
+    ///
+    /// Emphasis isn't balanced by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("this is **really** cool").soft_limit(14);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> this is **real…");
+    /// assert_eq!(blockquote.balance_emphasis(true).to_string(), "> this is **real**…");
+    /// ```
+    pub const fn balance_emphasis(mut self, balance_emphasis: bool) -> Self {
+        self.balance_emphasis = balance_emphasis;
+
+        self
+    }
+
+    /// Whether to isolate the content of each quoted line from the direction
+    /// of surrounding text.
+    ///
+    /// The content of each quoted line is wrapped in a first strong isolate
+    /// (U+2068) and pop directional isolate (U+2069), keeping mixed
+    /// right-to-left and left-to-right text readable. The isolates don't count
+    /// toward the soft and hard limits, and the ellipsis is written inside
+    /// them.
+    ///
+    /// Lines aren't isolated by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("مرحبا\n\nhello").bidi_isolate(true);
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> \u{2068}مرحبا\u{2069}\n> \n> \u{2068}hello\u{2069}",
+    /// );
+    /// ```
+    pub const fn bidi_isolate(mut self, bidi_isolate: bool) -> Self {
+        self.bidi_isolate = bidi_isolate;
+
+        self
+    }
+
+    /// Set the format the blockquote is rendered in.
+    ///
+    /// The text is cut off the same way in every format, and the
+    /// [maximum bytes] cover the rendered output.
+    ///
+    /// Blockquotes are rendered in markdown by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, Format};
+    ///
+    /// let blockquote = Blockquote::new("1 < 2\nyes\n\nhey, this is cool!").soft_limit(20);
+    ///
+    /// assert_eq!(
+    ///     blockquote.render(Format::Html).to_string(),
+    ///     "<blockquote><p>1 &lt; 2<br>yes</p><p>hey, this&hellip;</p></blockquote>",
+    /// );
+    /// ```
+    ///
+    /// [maximum bytes]: Self::max_bytes
+    pub const fn render(mut self, format: Format) -> Self {
+        self.render = format;
+
+        self
+    }
+
+    /// Set the string lines are indented by when [rendered] as plain text.
+    ///
+    /// The indentation counts towards the [maximum bytes].
+    ///
+    /// Lines are indented by 4 spaces by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, Format};
+    ///
+    /// let blockquote = Blockquote::new("hey,\n\nthis is cool!").render(Format::Plain);
+    ///
+    /// assert_eq!(blockquote.to_string(), "    hey,\n\n    this is cool!");
+    /// assert_eq!(blockquote.plain_indent("\t").to_string(), "\they,\n\n\tthis is cool!");
+    /// ```
+    ///
+    /// [maximum bytes]: Self::max_bytes
+    /// [rendered]: Self::render
+    pub const fn plain_indent(mut self, plain_indent: &'a str) -> Self {
+        self.plain_indent = plain_indent;
+
+        self
+    }
+
+    /// Set the number of spaces lines are indented by when [rendered] in
+    /// reStructuredText.
+    ///
+    /// The indentation counts towards the [maximum bytes].
+    ///
+    /// Lines are indented by 4 spaces by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, Format};
+    ///
+    /// let blockquote = Blockquote::new("hey,\n\nthis is cool!").render(Format::Rst);
+    ///
+    /// assert_eq!(blockquote.to_string(), "    hey,\n\n    this is cool!");
+    /// assert_eq!(blockquote.rst_indent(2).to_string(), "  hey,\n\n  this is cool!");
+    /// ```
+    ///
+    /// [maximum bytes]: Self::max_bytes
+    /// [rendered]: Self::render
+    pub const fn rst_indent(mut self, rst_indent: usize) -> Self {
+        self.rst_indent = rst_indent;
+
+        self
+    }
+
+    /// Set how bidirectional control characters in the text are sanitized.
+    ///
+    /// The embedding and override controls (U+202A to U+202E) and isolate
+    /// controls (U+2066 to U+2069) can reorder text after them, including text
+    /// after the blockquote. Escaped controls count toward the soft and hard
+    /// limits as their escapes, while removed controls don't count, and
+    /// closing controls written at the end of lines don't count either.
+    ///
+    /// Controls are kept as-is by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{BidiPolicy, Blockquote};
+    ///
+    /// let blockquote = Blockquote::new("file\u{202E}txt.exe");
+    ///
+    /// assert_eq!(
+    ///     blockquote.sanitize_bidi(BidiPolicy::Strip).to_string(),
+    ///     "> filetxt.exe",
+    /// );
+    /// assert_eq!(
+    ///     blockquote.sanitize_bidi(BidiPolicy::Escape).to_string(),
+    ///     "> file\\u{202E}txt.exe",
+    /// );
+    /// assert_eq!(
+    ///     blockquote.sanitize_bidi(BidiPolicy::Close).to_string(),
+    ///     "> file\u{202E}txt.exe\u{202C}",
+    /// );
+    /// ```
+    pub const fn sanitize_bidi(mut self, policy: BidiPolicy) -> Self {
+        self.sanitize_bidi = policy;
+
+        self
+    }
+
+    /// Set how ASCII control characters in the text are formatted.
+    ///
+    /// Controls such as NUL, BEL, and ESC can break terminals and markdown
+    /// renderers. Escaped controls count toward the soft and hard limits as
+    /// their escapes, while removed controls don't count.
+    ///
+    /// Controls are kept as-is by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, ControlPolicy};
+    ///
+    /// let blockquote = Blockquote::new("ding\x07 done");
+    ///
+    /// assert_eq!(
+    ///     blockquote.control_chars(ControlPolicy::Escape).to_string(),
+    ///     "> ding\\x07 done",
+    /// );
+    /// assert_eq!(
+    ///     blockquote.control_chars(ControlPolicy::Strip).to_string(),
+    ///     "> ding done",
+    /// );
+    /// ```
+    pub const fn control_chars(mut self, policy: ControlPolicy) -> Self {
+        self.control_chars = policy;
+
+        self
+    }
+
+    /// Set the boundary truncation backs off to.
+    ///
+    /// When the text is cut within a list item, it's cut at the end of the
+    /// previous item of the list instead, including items nested in it. If the
+    /// first item of the list doesn't fit, the text is cut as usual.
+    ///
+    /// Text is cut as usual by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, Boundary};
+    ///
+    /// let blockquote = Blockquote::new("- first\n- second item").soft_limit(16);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> - first\n> - second…");
+    /// assert_eq!(
+    ///     blockquote.break_on(Boundary::ListItem).to_string(),
+    ///     "> - first…",
+    /// );
+    /// ```
+    pub const fn break_on(mut self, boundary: Boundary) -> Self {
+        self.break_on = Some(boundary);
+
+        self
+    }
+
+    /// Whether to close a code fence left open by truncation.
+    ///
+    /// When the text is truncated inside a fenced code block, a closing fence
+    /// is written on its own quoted line, followed by the ellipsis on its own
+    /// line, keeping the formatted markdown valid. Closing fences use the
+    /// opening fence's character and length, and don't count toward the soft
+    /// and hard limits. Text is cut before an opening fence whose marker
+    /// doesn't fit entirely.
+    ///
+    /// Code fences aren't closed by default, unless keeping [inline markdown]
+    /// whole.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("```\nfirst\nsecond\n```")
+    ///     .soft_limit(9)
+    ///     .close_code_fences(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> ```\n> first\n> ```\n> …");
+    /// ```
+    ///
+    /// [inline markdown]: Self::inline_markdown_aware
+    pub const fn close_code_fences(mut self, close_code_fences: bool) -> Self {
+        self.close_code_fences = close_code_fences;
+
+        self
+    }
+
+    /// Whether to remove the leading whitespace common to all lines.
+    ///
+    /// The common indentation is the longest run of leading whitespace shared
+    /// exactly by every line with content, so lines indented with a mix of
+    /// tabs and spaces only lose the indentation that is identical. Blank
+    /// lines don't affect the common indentation. Removed indentation doesn't
+    /// count toward the soft and hard limits.
+    ///
+    /// Indentation is kept by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("    fn main() {\n        todo!();\n    }").dedent(true);
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> fn main() {\n>     todo!();\n> }",
+    /// );
+    /// ```
+    pub const fn dedent(mut self, dedent: bool) -> Self {
+        self.dedent = dedent;
+
+        self
+    }
+
+    /// Whether to quote the text as a unified diff.
+    ///
+    /// The first character of each line is its marker, such as `+`, `-`, or a
+    /// space, and is formatted verbatim. Markers aren't removed as whitespace:
+    /// context lines of a single space aren't blank, [dedenting] is disabled,
+    /// and trailing whitespace is trimmed after the marker when [preserving
+    /// hard breaks].
+    ///
+    /// Text isn't quoted as a diff by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new(" a\n-b\n+c\n ").dedent(true).diff_mode(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), ">  a\n> -b\n> +c\n>  ");
+    /// ```
+    ///
+    /// [dedenting]: Self::dedent
+    /// [preserving hard breaks]: Self::preserve_hard_breaks
+    pub const fn diff_mode(mut self, diff_mode: bool) -> Self {
+        self.diff_mode = diff_mode;
+
+        self
+    }
+
+    /// Whether to escape quote markers at the start of lines, so they're
+    /// formatted literally rather than nesting the blockquote.
+    ///
+    /// A quote marker preceded by at most three spaces at the start of a line
+    /// is escaped with a backslash. Quote markers elsewhere in lines are left
+    /// as-is. Backslashes count toward the soft and hard limits. To nest quoted
+    /// lines one level deeper instead, use [`nest_existing`].
+    ///
+    /// Quote markers aren't escaped by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("> quoted\nmid -> line").escape_existing_quotes(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> \\> quoted\n> mid -> line");
+    /// ```
+    ///
+    /// [`nest_existing`]: Self::nest_existing
+    pub const fn escape_existing_quotes(mut self, escape_existing_quotes: bool) -> Self {
+        self.escape_existing_quotes = escape_existing_quotes;
+
+        self
+    }
+
+    /// Whether to escape markdown punctuation in the text, so it's rendered
+    /// literally rather than interpreted as markdown.
+    ///
+    /// This is useful for quoting text that isn't markdown, such as log lines
+    /// and code identifiers like `*args`. This is shorthand for [escaping]
+    /// with [`EscapeProfile::Markdown`].
+    ///
+    /// Markdown isn't escaped by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("call f(*args)").escape_markdown(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), r"> call f\(\*args\)");
+    /// ```
+    ///
+    /// [escaping]: Self::escape
+    pub const fn escape_markdown(mut self, escape_markdown: bool) -> Self {
+        self.escape = if escape_markdown {
+            Some(EscapeProfile::Markdown)
+        } else {
+            None
+        };
+
+        self
+    }
+
+    /// Set the rules for escaping punctuation in the text with backslashes,
+    /// so it's rendered literally.
+    ///
+    /// Backslashes count toward the soft and hard limits. The prefixes and
+    /// ellipsis written by the formatter aren't escaped, and neither are the
+    /// other strings written verbatim, such as the [header] and the
+    /// [`truncation_suffix`].
+    ///
+    /// Text isn't escaped by default.
+    ///
+    /// # Examples
+    ///
+    /// Quote text for a Telegram message with the MarkdownV2 parse mode:
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, EscapeProfile};
+    ///
+    /// let blockquote = Blockquote::new("1 + 1 = 2.").escape(EscapeProfile::TelegramMarkdownV2);
+    ///
+    /// assert_eq!(blockquote.to_string(), r"> 1 \+ 1 \= 2\.");
+    /// ```
+    ///
+    /// [header]: Self::header
+    /// [`truncation_suffix`]: Self::truncation_suffix
+    pub const fn escape(mut self, profile: EscapeProfile) -> Self {
+        self.escape = Some(profile);
+
+        self
+    }
+
+    /// Whether to keep inline markdown whole when truncating.
+    ///
+    /// Code spans, delimited by matching runs of backticks on a line, are
+    /// never cut, so the rest of the document isn't formatted as code. Nor are
+    /// inline links and images like `[text](url)`, reference links like
+    /// `[text][ref]`, autolinks like `<https://example.com>`, and HTML tags
+    /// like `<img alt="a cat">`. Text is cut
+    /// before inline markdown that doesn't fit, along with the whitespace
+    /// preceding it, even if the inline markdown alone exceeds the limits.
+    /// Markdown within fenced code blocks isn't inline markdown, and code
+    /// fences left open by truncation are [closed].
+    ///
+    /// Rows of tables are never cut either. Text is cut before the first row
+    /// that doesn't fit, or before the whole table if its header and delimiter
+    /// rows don't fit. The ellipsis of a truncated table follows it after a
+    /// blank line, since any other line would continue the table.
+    ///
+    /// Inline markdown may be truncated by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("run `cargo test` first").soft_limit(10);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> run `cargo…");
+    /// assert_eq!(blockquote.inline_markdown_aware(true).to_string(), "> run…");
+    ///
+    /// let blockquote = Blockquote::new("see [the docs](https://docs.rs)").soft_limit(13);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> see [the docs…");
+    /// assert_eq!(blockquote.inline_markdown_aware(true).to_string(), "> see…");
+    /// ```
+    ///
+    /// [closed]: Self::close_code_fences
+    pub const fn inline_markdown_aware(mut self, inline_markdown_aware: bool) -> Self {
+        self.inline_markdown_aware = inline_markdown_aware;
+
+        self
+    }
+
+    /// Set the text written after the prefix of empty quoted lines.
+    ///
+    /// Some renderers collapse quoted lines without content. Filling them
+    /// with a non-breaking or zero-width space keeps them visible. The fill
+    /// doesn't count toward the soft and hard limits.
+    ///
+    /// Empty lines aren't filled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("first\n\nsecond").empty_line_fill("\u{a0}");
+    ///
+    /// assert_eq!(blockquote.to_string(), "> first\n> \u{a0}\n> second");
+    /// ```
+    pub const fn empty_line_fill(mut self, empty_line_fill: &'a str) -> Self {
+        self.empty_line_fill = empty_line_fill;
+
+        self
+    }
+
+    /// Expand tabs into spaces up to the next tab stop.
+    ///
+    /// Tab stops are placed every `tab_size` columns, counted from the start
+    /// of each quoted line's content. Expanded tabs count toward the soft and
+    /// hard limits and the wrap width as their spaces, and are only formatted
+    /// if all of their spaces fit within the limit. A tab size of zero removes
+    /// tabs.
+    ///
+    /// Tabs are kept by default, except for those indenting lines of
+    /// markdown. The quote marker moves the tab stops, so those are expanded
+    /// to the tab stops of markdown, every four columns, to keep lines as
+    /// indented as they were.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("a\tb\n\tc").expand_tabs(4);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> a   b\n>     c");
+    /// ```
+    pub const fn expand_tabs(mut self, tab_size: usize) -> Self {
+        self.tab_size = Some(tab_size);
+
+        self
+    }
+
+    /// Set the indentation written before the start of every quoted line.
+    ///
+    /// This is useful for nesting a blockquote under a list item, which
+    /// requires the blockquote to be indented to the list item's content.
+    ///
+    /// There is no indentation by default.
+    ///
+    /// # Examples
+    ///
+    /// Nest a blockquote under a list item:
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("quoted\ntext").indent("  ");
+    ///
+    /// assert_eq!(blockquote.to_string(), "  > quoted\n  > text");
+    /// ```
+    pub const fn indent(mut self, indent: &'a str) -> Self {
+        self.indent = indent;
+
+        self
+    }
+
+    /// Set additional characters considered whitespace when checking for
+    /// blank lines and [empty] text.
+    ///
+    /// Blank leading and trailing lines aren't formatted, and neither is
+    /// trailing whitespace, so this allows treating characters such as
+    /// braille pattern blanks or the dashes of rules like any other
+    /// whitespace there. The characters are still formatted within the text.
+    ///
+    /// Only characters that are [`char::is_whitespace`] are whitespace by
+    /// default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("\u{2800}\n--\n");
+    ///
+    /// assert!(!blockquote.is_empty());
+    /// assert!(blockquote.whitespace_chars("\u{2800}-").is_empty());
+    /// assert_eq!(blockquote.whitespace_chars("\u{2800}-").to_string(), "");
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    pub const fn whitespace_chars(mut self, whitespace_chars: &'a str) -> Self {
+        self.whitespace_chars = whitespace_chars;
+
+        self
+    }
+
+    /// Set a prefix that lines of the text must start with to be quoted.
+    ///
+    /// Other lines are skipped entirely, as if they weren't in the text, and
+    /// don't count toward the limits. Blank lines don't start with the prefix,
+    /// so they're skipped too, and the quoted lines are never separated by
+    /// blank lines. Lines are matched as they are in the text, before quote
+    /// markers or indentation are removed.
+    ///
+    /// All lines are quoted by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let text = "INFO starting\nERROR no disk\n\nINFO retrying\nERROR no disk";
+    /// let blockquote = Blockquote::new(text).filter_lines_starting_with("ERROR");
+    ///
+    /// assert_eq!(blockquote.to_string(), "> ERROR no disk\n> ERROR no disk");
+    /// ```
+    pub const fn filter_lines_starting_with(mut self, prefix: &'a str) -> Self {
+        self.filter_lines_starting_with = Some(prefix);
+
+        self
+    }
+
+    /// Whether text consisting only of zero-width and formatting characters,
+    /// such as zero-width spaces and byte order marks, is [empty].
+    ///
+    /// These characters aren't whitespace, but text with only them and
+    /// whitespace renders as a visually empty quote.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("\u{200B}\u{200B}");
+    ///
+    /// assert!(!blockquote.is_empty());
+    /// assert!(blockquote.zero_width_is_empty(true).is_empty());
+    /// assert_eq!(blockquote.zero_width_is_empty(true).to_string(), "");
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    pub const fn zero_width_is_empty(mut self, zero_width_is_empty: bool) -> Self {
+        self.zero_width_is_empty = zero_width_is_empty;
+
+        self
+    }
+
+    /// Whether to skip the blank lines at the start of the text.
+    ///
+    /// When skipped, the blockquote starts with the first line with content,
+    /// without the leading spaces and tabs of that line. Otherwise leading
+    /// blank lines are formatted as empty quoted lines. Text consisting only
+    /// of whitespace is [empty] either way.
+    ///
+    /// Leading blank lines are skipped by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("\n \n  hello");
+    ///
+    /// assert_eq!(blockquote.to_string(), "> hello");
+    /// assert_eq!(
+    ///     blockquote.trim_leading_blank_lines(false).to_string(),
+    ///     "> \n>  \n>   hello",
+    /// );
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    pub const fn trim_leading_blank_lines(mut self, trim_leading_blank_lines: bool) -> Self {
+        self.trim_leading_blank_lines = trim_leading_blank_lines;
+
+        self
+    }
+
+    /// Whether to keep the blank lines at the end of the text.
+    ///
+    /// Trailing blank lines are formatted as empty quoted lines, which is
+    /// useful for quoting text verbatim. Text consisting only of whitespace
+    /// is still [empty].
+    ///
+    /// Trailing blank lines are removed by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("test\n\n").keep_trailing_blank_lines(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> test\n> \n> ");
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    pub const fn keep_trailing_blank_lines(mut self, keep_trailing_blank_lines: bool) -> Self {
+        self.keep_trailing_blank_lines = keep_trailing_blank_lines;
+
+        self
+    }
+
+    /// Whether to only prefix the first line of each paragraph, relying on
+    /// lazy continuation for the rest of the paragraph.
+    ///
+    /// Markdown continues a quoted paragraph onto following lines without
+    /// quote markers, so lines continuing a paragraph, including lines
+    /// produced by wrapping, are formatted without a prefix. Blank lines are
+    /// still prefixed so the blockquote doesn't end. Lines that could start
+    /// another block, such as list items, headings, and code, and lines
+    /// within fenced code blocks are always prefixed, as are lines nested at a
+    /// different depth than the line before them.
+    ///
+    /// Every line is prefixed by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("first\nline\n\n- second").lazy(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> first\nline\n> \n> - second");
+    /// ```
+    pub const fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+
+        self
+    }
+
+    /// Whether to remove quote markers at the start of lines, formatting all
+    /// lines at a single quote level.
+    ///
+    /// Quote markers are recognized as with [`nest_existing`], which this takes
+    /// precedence over. Removed quote markers don't count toward the soft and
+    /// hard limits, and lines consisting only of quote markers are blank, so
+    /// they're trimmed like other blank lines. [Stripped] ANSI escape
+    /// sequences are skipped among the markers.
+    ///
+    /// Quote markers are formatted as content by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("> > original\n> reply\nanswer").flatten_nested(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> original\n> reply\n> answer");
+    /// ```
+    ///
+    /// [`nest_existing`]: Self::nest_existing
+    /// [Stripped]: Self::strip_ansi
+    pub const fn flatten_nested(mut self, flatten_nested: bool) -> Self {
+        self.flatten_nested = flatten_nested;
+
+        self
+    }
+
+    /// Whether to nest lines already quoted in the text one level deeper.
+    ///
+    /// Quote markers at the start of each line, each optionally preceded by up
+    /// to three spaces and followed by a space, are removed and the line is
+    /// formatted with one more quote marker than it had, bumping every quote
+    /// by one level while keeping their relative nesting. Lines without quote
+    /// markers are quoted once, and markers elsewhere in lines are left as-is.
+    /// Removed quote markers don't count toward the soft and hard limits. This
+    /// is the inverse of [`escape_existing_quotes`], which it takes precedence
+    /// over.
+    ///
+    /// Quote markers are formatted as content by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new(">quoted\n>>  nested\nreply -> yes").nest_existing(true);
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> > quoted\n> > >  nested\n> reply -> yes",
+    /// );
+    /// ```
+    ///
+    /// [`escape_existing_quotes`]: Self::escape_existing_quotes
+    pub const fn nest_existing(mut self, nest_existing: bool) -> Self {
+        self.nest_existing = nest_existing;
+
+        self
+    }
+
+    /// Set which lines are prefixed with quote markers.
+    ///
+    /// Markdown lets lines of a paragraph lazily continue a blockquote without
+    /// quote markers, so prefixing only the first line suits quotes of a
+    /// single paragraph. Blank lines and lines starting other blocks, such as
+    /// list items, end the blockquote instead. Lines continuing a wrapped line
+    /// are still prefixed with the [wrap continuation] if set. To only omit
+    /// prefixes where it's safe, use [`lazy`].
+    ///
+    /// Every line is prefixed by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, PrefixMode};
+    ///
+    /// let blockquote = Blockquote::new("one\ntwo").prefix_mode(PrefixMode::FirstOnly);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> one\ntwo");
+    /// ```
+    ///
+    /// [`lazy`]: Self::lazy
+    /// [wrap continuation]: Self::wrap_continuation
+    pub const fn prefix_mode(mut self, prefix_mode: PrefixMode) -> Self {
+        self.prefix_mode = prefix_mode;
+
+        self
+    }
+
+    /// Set how the quote markers of nested lines are joined.
+    ///
+    /// Lines are nested when [`nest_existing`] is enabled.
+    ///
+    /// Markers are separated by spaces by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, NestingStyle};
+    ///
+    /// let blockquote = Blockquote::new("> > nested")
+    ///     .nest_existing(true)
+    ///     .nesting_style(NestingStyle::Contiguous);
+    ///
+    /// assert_eq!(blockquote.to_string(), ">>> nested");
+    /// ```
+    ///
+    /// [`nest_existing`]: Self::nest_existing
+    pub const fn nesting_style(mut self, nesting_style: NestingStyle) -> Self {
+        self.nesting_style = nesting_style;
+
+        self
+    }
+
+    /// Set the limit to cut off each line of the text at.
+    ///
+    /// Each line is cut off independently, ending with the ellipsis if it was
+    /// cut off, while the soft and hard limits still apply to the text as a
+    /// whole. The per-line limit is measured in the [`limit_unit`].
+    ///
+    /// There is no per-line limit by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("aaaaaa\nbb\ncccccc").per_line_limit(4);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> aaaa…\n> bb\n> cccc…");
+    /// ```
+    ///
+    /// [`limit_unit`]: Self::limit_unit
+    pub const fn per_line_limit(mut self, per_line_limit: usize) -> Self {
+        self.per_line_limit = Some(per_line_limit);
+
+        self
+    }
+
+    /// Whether to preserve hard line breaks while trimming other trailing
+    /// whitespace of lines.
+    ///
+    /// Lines ending in two or more spaces, a markdown hard line break, end in
+    /// exactly two spaces, even if they would exceed the [wrap] width. Other
+    /// trailing whitespace is trimmed. Hard line breaks ending in a backslash
+    /// are formatted like any other text.
+    ///
+    /// Trailing whitespace is formatted as-is by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("roses    \nviolets \nsugar").preserve_hard_breaks(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> roses  \n> violets\n> sugar");
+    /// ```
+    ///
+    /// [wrap]: Self::wrap
+    pub const fn preserve_hard_breaks(mut self, preserve_hard_breaks: bool) -> Self {
+        self.preserve_hard_breaks = preserve_hard_breaks;
+
+        self
+    }
+
+    /// Whether line breaks between lines of text are written as `<br>` line
+    /// breaks, for renderers ignoring markdown hard line breaks.
+    ///
+    /// A `<br>` ends each line followed by another line of the same
+    /// paragraph, rather than a blank line or the start of another block, and
+    /// lines of code blocks don't end with one. It doesn't count toward
+    /// the soft and hard limits, and lines produced by [wrapping] don't end
+    /// with one.
+    ///
+    /// This is disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("roses\nviolets\n\nsugar").html_line_breaks(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> roses<br>\n> violets\n> \n> sugar");
+    /// ```
+    ///
+    /// [wrapping]: Self::wrap
+    pub const fn html_line_breaks(mut self, html_line_breaks: bool) -> Self {
+        self.html_line_breaks = html_line_breaks;
+
+        self
+    }
+
+    /// Set the width to wrap lines exceeding it onto new quoted lines at.
+    ///
+    /// Lines are wrapped at whitespace, which is consumed by the wrap, so the
+    /// content of no quoted line exceeds the width. Words longer than the
+    /// width are broken at the width, except for URLs, which are kept whole on
+    /// their own lines. Line breaks in the text are preserved.
+    ///
+    /// The width is independent of the soft and hard limits: the limits bound
+    /// the total number of characters formatted, while the width bounds each
+    /// individual line. Line breaks inserted by wrapping don't count toward
+    /// the limits, but the whitespace they replace does.
+    ///
+    /// Lines aren't wrapped by default.
+    ///
+    /// # Examples
+    ///
+    /// Wrap text to 12 characters per line while truncating at 30 characters:
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("the quick brown fox jumps over the lazy dog")
+    ///     .soft_limit(30)
+    ///     .wrap(12);
+    ///
+    /// assert_eq!(
+    ///     blockquote.to_string(),
+    ///     "> the quick\n> brown fox\n> jumps over…",
+    /// );
+    /// ```
+    pub const fn wrap(mut self, width: usize) -> Self {
+        self.wrap = Some(width);
+
+        self
+    }
+
+    /// Set the prefix of lines produced by [wrapping], replacing their
+    /// indentation and quote markers.
+    ///
+    /// Lines separated by line breaks in the text use the usual prefix, so a
+    /// prefix such as `">   "` distinguishes continuation lines with a hanging
+    /// indent. Like the usual prefix, the continuation prefix doesn't count
+    /// toward the wrap width or the soft and hard limits.
+    ///
+    /// Wrapped lines use the usual prefix by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("- one two three\n- four")
+    ///     .wrap(9)
+    ///     .wrap_continuation(">   ");
+    ///
+    /// assert_eq!(blockquote.to_string(), "> - one two\n>   three\n> - four");
+    /// ```
+    ///
+    /// [wrapping]: Self::wrap
+    pub const fn wrap_continuation(mut self, wrap_continuation: &'a str) -> Self {
+        self.wrap_continuation = Some(wrap_continuation);
+
+        self
+    }
+
+    /// Set the unit the soft and hard limits are measured in.
+    ///
+    /// Limits are measured in [characters] by default.
+    ///
+    /// # Examples
+    ///
+    /// Limits measured in bytes cut multi-byte text earlier:
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, LimitUnit};
+    ///
+    /// let blockquote = Blockquote::new("naïve café").soft_limit(8);
+    /// assert_eq!(blockquote.to_string(), "> naïve ca…");
+    ///
+    /// let blockquote = blockquote.limit_unit(LimitUnit::Bytes);
+    /// assert_eq!(blockquote.to_string(), "> naïve c…");
+    /// ```
+    ///
+    /// [characters]: LimitUnit::Chars
+    pub const fn limit_unit(mut self, limit_unit: LimitUnit) -> Self {
+        self.limit_unit = limit_unit;
+
+        self
+    }
+
+    /// Whether to start the blockquote with a newline.
+    ///
+    /// This ensures the blockquote starts on its own line when written after
+    /// other text. Nothing is formatted if the blockquote is [empty].
+    ///
+    /// There is no leading newline by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hello").leading_newline(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "\n> hello");
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    pub const fn leading_newline(mut self, leading_newline: bool) -> Self {
+        self.leading_newline = leading_newline;
+
+        self
+    }
+
+    /// Set a timestamp written before the content of the first quoted line.
+    ///
+    /// The timestamp is written as-is after the line's prefix, followed by a
+    /// space unless it already ends with whitespace, so it can be formatted
+    /// however is needed. It doesn't count toward the soft and hard limits or
+    /// the wrap width. Lines without content and lines continuing a wrapped
+    /// line aren't timestamped. To timestamp every quoted line, use
+    /// [`timestamp_every_line`].
+    ///
+    /// There is no timestamp by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("message\ncontinued").timestamp("[12:01]");
+    ///
+    /// assert_eq!(blockquote.to_string(), "> [12:01] message\n> continued");
+    /// ```
+    ///
+    /// [`timestamp_every_line`]: Self::timestamp_every_line
+    pub const fn timestamp(mut self, timestamp: &'a str) -> Self {
+        self.timestamp = Some(timestamp);
+
+        self
+    }
+
+    /// Whether to write the [timestamp] before the content of every quoted
+    /// line rather than only the first.
+    ///
+    /// Only the first line is timestamped by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hi\n\nbye")
+    ///     .timestamp("[12:01]")
+    ///     .timestamp_every_line(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> [12:01] hi\n> \n> [12:01] bye");
+    /// ```
+    ///
+    /// [timestamp]: Self::timestamp
+    pub const fn timestamp_every_line(mut self, timestamp_every_line: bool) -> Self {
+        self.timestamp_every_line = timestamp_every_line;
+
+        self
+    }
+
+    /// Whether to strike through the content of each line.
+    ///
+    /// Content is wrapped in `~~` markers after the prefix of each line, so
+    /// every quoted line is struck through on its own. Markers are placed
+    /// around the whitespace at the start and end of lines, and the ellipsis
+    /// of truncated content is struck through with it. Lines without content
+    /// aren't struck through.
+    ///
+    /// Content isn't struck through by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("deleted\n\n  message").strikethrough(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> ~~deleted~~\n> \n>   ~~message~~");
+    /// ```
+    pub const fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+
+        self
+    }
+
+    /// Whether to skip the front matter starting the text, quoting only the
+    /// document after it.
+    ///
+    /// Front matter starts with a line of exactly `---` for YAML, closed by a
+    /// line of `---` or `...`, or `+++` for TOML, closed by a line of `+++`.
+    /// A blank line following the front matter is skipped too. If the front
+    /// matter isn't closed, the whole text is quoted.
+    ///
+    /// Front matter isn't skipped by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("---\ntitle: Notes\n---\n\nhey, this is cool!");
+    ///
+    /// assert_eq!(
+    ///     blockquote.strip_front_matter(true).to_string(),
+    ///     "> hey, this is cool!",
+    /// );
+    /// ```
+    pub const fn strip_front_matter(mut self, strip_front_matter: bool) -> Self {
+        self.strip_front_matter = strip_front_matter;
+
+        self
+    }
+
+    /// Whether to remove ANSI escape sequences from the text, such as those
+    /// coloring the output of commands.
+    ///
+    /// Control sequences, such as `\x1B[0;31m` for red text, and operating
+    /// system commands, such as those of hyperlinks, are removed and don't
+    /// count toward the soft and hard limits. Incomplete sequences and other
+    /// escapes are kept.
+    ///
+    /// Escape sequences aren't removed by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("\x1B[1;31merror:\x1B[0m no such file");
+    ///
+    /// assert_eq!(
+    ///     blockquote.strip_ansi(true).to_string(),
+    ///     "> error: no such file",
+    /// );
+    /// ```
+    pub const fn strip_ansi(mut self, strip_ansi: bool) -> Self {
+        self.strip_ansi = strip_ansi;
+
+        self
+    }
+
+    /// Whether to strip markdown from the text, quoting only its plain text.
+    ///
+    /// This is useful for previews. The markdown is parsed, so only the
+    /// emphasis and strikethrough delimiters that the parser matches are
+    /// removed, along with HTML and the markers of headings, quotes, and list
+    /// items. Links and images are replaced by their label and alt text, code
+    /// spans are unwrapped, and code blocks lose their fences. Thematic breaks
+    /// and link reference definitions are removed entirely, and blocks are
+    /// separated by a blank line.
+    ///
+    /// The limits measure the stripped text. Requires the `strip-markdown`
+    /// feature.
+    ///
+    /// Markdown isn't stripped by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("# Hi\n\n**see** [the docs](https://docs.rs)");
+    ///
+    /// assert_eq!(
+    ///     blockquote.strip_markdown(true).to_string(),
+    ///     "> Hi\n> \n> see the docs",
+    /// );
+    /// ```
+    #[cfg(feature = "strip-markdown")]
+    pub const fn strip_markdown(mut self, strip_markdown: bool) -> Self {
+        self.strip_markdown = strip_markdown;
+
+        self
+    }
+
+    /// Whether to squash runs of spaces and tabs within lines into a single
+    /// space.
+    ///
+    /// This tidies text extracted from HTML or PDFs. The indentation of lines
+    /// and line breaks are kept, and so are non-breaking spaces, which are
+    /// usually deliberate. Squashed whitespace counts toward the soft and hard
+    /// limits as a single space.
+    ///
+    /// Whitespace isn't squashed by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("  hey,  this is\t\tcool!  Right?");
+    ///
+    /// assert_eq!(
+    ///     blockquote.squash_whitespace(true).to_string(),
+    ///     ">   hey, this is cool! Right?",
+    /// );
+    /// ```
+    pub const fn squash_whitespace(mut self, squash_whitespace: bool) -> Self {
+        self.squash_whitespace = squash_whitespace;
+
+        self
+    }
+
+    /// Whether to remove zero-width characters from the text before measuring
+    /// it against the limits.
+    ///
+    /// The zero-width space (U+200B), non-joiner (U+200C), joiner (U+200D),
+    /// word joiner (U+2060), and byte order mark (U+FEFF) are removed. These
+    /// are often pasted accidentally or used to get around filters, and would
+    /// otherwise count toward the limits despite being invisible.
+    ///
+    /// Zero-width joiners between emoji are kept, as they join the emoji into
+    /// a single one, such as a family of people.
+    ///
+    /// Zero-width characters aren't removed by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey,\u{200B} this\u{2060} is cool!").soft_limit(9);
+    ///
+    /// assert_eq!(blockquote.strip_zero_width(true).to_string(), "> hey, this…");
+    /// ```
+    pub const fn strip_zero_width(mut self, strip_zero_width: bool) -> Self {
+        self.strip_zero_width = strip_zero_width;
+
+        self
+    }
+
+    /// Whether to end the blockquote with a newline.
+    ///
+    /// This prevents text written after the blockquote from continuing its
+    /// last line. The newline is written with the [line ending], without a
+    /// prefix, after everything else including the ellipsis of truncated
+    /// text. Nothing is formatted if the blockquote is [empty].
+    ///
+    /// There is no trailing newline by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hello").trailing_newline(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> hello\n");
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    /// [line ending]: Self::line_ending
+    pub const fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+
+        self
+    }
+
+    /// Set the suffix written after the last quoted line when the text is
+    /// truncated, replacing the ellipsis.
+    ///
+    /// The suffix isn't written if [`with_ellipsis`] is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("this text is too long")
+    ///     .soft_limit(9)
+    ///     .truncation_suffix(" […]");
+    ///
+    /// assert_eq!(blockquote.to_string(), "> this text […]");
+    /// ```
+    ///
+    /// [`with_ellipsis`]: Self::with_ellipsis
+    pub const fn truncation_suffix(mut self, truncation_suffix: &'a str) -> Self {
+        self.truncation_suffix = truncation_suffix;
+
+        self
+    }
+
+    /// Whether to treat Unicode line breaks other than newlines as line breaks.
+    ///
+    /// When enabled, a carriage return not followed by a newline, the next line
+    /// control (U+0085), the line separator (U+2028), and the paragraph
+    /// separator (U+2029) start a new quoted line. Paragraph separators are
+    /// followed by a blank quoted line.
+    ///
+    /// Only newlines, optionally preceded by a carriage return, are line breaks
+    /// by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("first\rsecond\u{2029}third").unicode_newlines(true);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> first\n> second\n> \n> third");
+    /// ```
+    pub const fn unicode_newlines(mut self, unicode_newlines: bool) -> Self {
+        self.unicode_newlines = unicode_newlines;
+
+        self
+    }
+
+    /// Set the line break written between lines.
+    ///
+    /// This applies to every line break, including the [leading] and
+    /// [trailing] newlines. Line breaks are counted toward the soft and hard
+    /// limits as a single character, or as the length of the line ending when
+    /// limits are measured in [bytes].
+    ///
+    /// Lines end with [`LineEnding::Lf`] by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::{Blockquote, LineEnding};
+    ///
+    /// let blockquote = Blockquote::new("first\nsecond").line_ending(LineEnding::CrLf);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> first\r\n> second");
+    /// ```
+    ///
+    /// [bytes]: LimitUnit::Bytes
+    /// [leading]: Self::leading_newline
+    /// [trailing]: Self::trailing_newline
+    pub const fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+
+        self
+    }
+
+    /// Whether the ellipsis of truncated text is written on its own quoted
+    /// line rather than at the end of the last line.
+    ///
+    /// Lines cut off by the [`per_line_limit`] still end with the ellipsis,
+    /// and the ellipsis on its own line isn't [struck through].
+    ///
+    /// The ellipsis is written at the end of the last line by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("first\nsecond").soft_limit(5);
+    /// assert_eq!(blockquote.to_string(), "> first…");
+    ///
+    /// let blockquote = blockquote.ellipsis_on_new_line(true);
+    /// assert_eq!(blockquote.to_string(), "> first\n> …");
+    /// ```
+    ///
+    /// [`per_line_limit`]: Self::per_line_limit
+    /// [struck through]: Self::strikethrough
+    pub const fn ellipsis_on_new_line(mut self, ellipsis_on_new_line: bool) -> Self {
+        self.ellipsis_on_new_line = ellipsis_on_new_line;
+
+        self
+    }
+
+    /// Set the marker written instead of the [truncation suffix] when it's
+    /// written [on its own line].
+    ///
+    /// This allows the line ending a cut off blockquote to differ from the
+    /// end of lines cut off by the [`per_line_limit`], which still end with
+    /// the truncation suffix.
+    ///
+    /// The truncation suffix is written on its own line too by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("first\nsecond")
+    ///     .soft_limit(5)
+    ///     .ellipsis_line_marker("⋯");
+    /// assert_eq!(blockquote.to_string(), "> first…");
+    ///
+    /// let blockquote = blockquote.ellipsis_on_new_line(true);
+    /// assert_eq!(blockquote.to_string(), "> first\n> ⋯");
+    /// ```
+    ///
+    /// [`per_line_limit`]: Self::per_line_limit
+    /// [on its own line]: Self::ellipsis_on_new_line
+    /// [truncation suffix]: Self::truncation_suffix
+    pub const fn ellipsis_line_marker(mut self, marker: &'a str) -> Self {
+        self.ellipsis_line_marker = Some(marker);
+
+        self
+    }
+
+    /// Whether the ellipsis replaces the end of truncated text rather than
+    /// being written after it.
+    ///
+    /// When the text is truncated, room is reserved for the ellipsis (or the
+    /// [`truncation_suffix`]) within the soft limit, so the formatted text
+    /// including the ellipsis never exceeds the limit. Text that fits within
+    /// the limit isn't affected. The ellipsis is left out if the limit is too
+    /// small to hold it.
+    ///
+    /// The ellipsis is written after truncated text by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("this text is too long").soft_limit(10);
+    /// assert_eq!(blockquote.to_string(), "> this text …");
+    ///
+    /// let blockquote = blockquote.ellipsis_replaces(true);
+    /// assert_eq!(blockquote.to_string(), "> this text…");
+    /// ```
+    ///
+    /// [`truncation_suffix`]: Self::truncation_suffix
+    pub const fn ellipsis_replaces(mut self, ellipsis_replaces: bool) -> Self {
+        self.ellipsis_replaces = ellipsis_replaces;
+
+        self
+    }
+
+    /// Whether to include ellipsis upon reaching the end of the formatting.
+    ///
+    /// Disabling ellipsis also disables the [`truncation_suffix`].
+    ///
+    /// Ellipsis are included by default.
+    ///
+    /// [`truncation_suffix`]: Self::truncation_suffix
+    pub const fn with_ellipsis(mut self, with_ellipsis: bool) -> Self {
+        self.with_ellipsis = with_ellipsis;
+
+        self
+    }
+
+    /// Create an owned copy of the blockquote, which doesn't borrow its text or
+    /// configuration.
+    ///
+    /// Lines of a blockquote created with [`from_lines`] are joined by
+    /// newlines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let text = String::from("owned text");
+    /// let blockquote = Blockquote::new(&text).soft_limit(5).to_owned();
+    /// drop(text);
+    ///
+    /// assert_eq!(blockquote.to_string(), "> owned…");
+    /// ```
+    ///
+    /// [`from_lines`]: Self::from_lines
+    #[cfg(feature = "alloc")]
+    pub fn to_owned(&self) -> BlockquoteBuf {
+        BlockquoteBuf::from_blockquote(self)
+    }
+
+    /// Create an owned copy of the blockquote with each line of its text
+    /// transformed.
+    ///
+    /// Lines are transformed before anything else, so the prefix and limits
+    /// apply to the transformed lines, which are joined by newlines.
+    /// Transformed lines containing line breaks are split into more lines.
+    ///
+    /// # Examples
+    ///
+    /// Redact a password:
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("user: admin\npass: hunter2")
+    ///     .map_lines(|line| line.replace("hunter2", "[redacted]"));
+    ///
+    /// assert_eq!(blockquote.to_string(), "> user: admin\n> pass: [redacted]");
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn map_lines<F, S>(&self, mut f: F) -> BlockquoteBuf
+    where
+        F: FnMut(&str) -> S,
+        S: AsRef<str>,
+    {
+        let mut text = alloc::string::String::new();
+
+        for (index, line) in self.content().lines(self.unicode_newlines).enumerate() {
+            if index > 0 {
+                text.push(NEWLINE);
+            }
+
+            text.push_str(f(line.text).as_ref());
+        }
+
+        self.to_owned().text(text).strip_front_matter(false)
+    }
+
+    /// Blockquote of only the first line of the text with content, for compact
+    /// previews.
+    ///
+    /// The ellipsis is appended if the text has more content after the line,
+    /// or if the line itself is cut off by a limit. Leading blank lines are
+    /// always skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("\nhey, this is cool!\nright?");
+    ///
+    /// assert_eq!(blockquote.first_line().to_string(), "> hey, this is cool!…");
+    /// assert_eq!(
+    ///     blockquote.soft_limit(9).first_line().to_string(),
+    ///     "> hey, this…",
+    /// );
+    /// ```
+    pub fn first_line(&self) -> Self {
+        let cut = self
+            .content()
+            .lines(self.unicode_newlines)
+            .find(|line| !line.text.trim().is_empty())
+            .map(|line| line.position + line.text.trim_end().len());
+
+        Self {
+            cut,
+            trim_leading_blank_lines: true,
+            ..*self
+        }
+    }
+
+    /// Whether the blockquote will be empty upon formatting.
+    ///
+    /// This will be the case if the input text is empty or only consists of
+    /// whitespace, including [additional whitespace characters], and
+    /// zero-width characters if they're [considered empty]. If markdown is
+    /// stripped, the stripped text is inspected. It's also the case if less
+    /// than the [minimum characters] would be formatted and nothing is
+    /// formatted instead, or if not even the start of the blockquote fits the
+    /// [maximum bytes]. Other options may leave nothing to format too, such
+    /// as [stripping headings] of lines with nothing but a heading marker, or
+    /// a soft limit of zero [without an ellipsis], in any format, as the
+    /// blockquote is formatted until anything is written to check.
+    ///
+    /// Blockquotes will short circuit and format nothing when empty. To check
+    /// in const contexts, use [`is_definitely_empty`].
+    ///
+    /// [`is_definitely_empty`]: Self::is_definitely_empty
+    /// [additional whitespace characters]: Self::whitespace_chars
+    /// [considered empty]: Self::zero_width_is_empty
+    /// [maximum bytes]: Self::max_bytes
+    /// [minimum characters]: Self::min_chars
+    /// [stripping headings]: Self::headings
+    /// [without an ellipsis]: Self::with_ellipsis
+    pub fn is_empty(&self) -> bool {
+        // Formatting fails as soon as anything is written.
+        self.format(&mut Unwritten).is_ok()
+    }
+
+    /// Whether every line of the text is blank or removed, in which case
+    /// nothing is formatted.
+    fn is_blank_text(&self) -> bool {
+        self.content().lines(self.unicode_newlines).all(|line| {
+            let text = &line.text[self.flattened_len(line.text)..];
+
+            if self.is_filtered_out(Some(line.text))
+                || self.image_line(Some(line.text), None)
+                || self.is_heading_marker(text, None)
+            {
+                return true;
+            }
+
+            let len = self.content_len(text, |character| {
+                self.is_blank(character)
+                    || self.zero_width_is_empty && is_zero_width(character)
+                    || self.is_removed(character)
+            });
+
+            len == 0
+        })
+    }
+
+    /// Whether the input text is empty, without inspecting its characters.
+    ///
+    /// Unlike [`is_empty`], text consisting only of whitespace isn't
+    /// definitely empty, so a blockquote that isn't definitely empty may still
+    /// be [empty][`is_empty`]. This is usable in const contexts, such as
+    /// validating configuration at compile time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// const BLOCKQUOTE: Blockquote<'static> = Blockquote::new(" ");
+    /// const EMPTY: bool = BLOCKQUOTE.is_definitely_empty();
+    ///
+    /// assert!(!EMPTY);
+    /// assert!(BLOCKQUOTE.is_empty());
+    /// ```
+    ///
+    /// [`is_empty`]: Self::is_empty
+    pub const fn is_definitely_empty(&self) -> bool {
+        match self.source {
+            Source::Lines(lines) => {
+                let mut index = 0;
+
+                while index < lines.len() {
+                    if !lines[index].is_empty() {
+                        return false;
+                    }
+
+                    index += 1;
+                }
+
+                true
+            }
+            Source::Text(text) => text.is_empty(),
+        }
+    }
+
+    /// Text being quoted, without its front matter if it's skipped.
+    fn content(&self) -> Source<'a> {
+        if self.strip_front_matter {
+            self.source.after_front_matter(self.unicode_newlines)
+        } else {
+            self.source
+        }
+    }
+
+    /// Length of a line up to the end of its last character that isn't blank,
+    /// skipping ANSI escape sequences if they're removed.
+    fn content_len(&self, line: &str, is_blank: impl Fn(char) -> bool) -> usize {
+        if !self.strip_ansi {
+            return line.trim_end_matches(is_blank).len();
+        }
+
+        let mut len = 0;
+        let mut index = 0;
+
+        while let Some(character) = line[index..].chars().next() {
+            if let Some(ansi) = ansi_len(&line[index..]) {
+                index += ansi;
+
+                continue;
+            }
+
+            index += character.len_utf8();
+
+            if !is_blank(character) {
+                len = index;
+            }
+        }
+
+        len
+    }
+
+    /// Whether a line is skipped for not starting with the [filtered prefix].
+    ///
+    /// [filtered prefix]: Self::filter_lines_starting_with
+    fn is_filtered_out(&self, line: Option<&str>) -> bool {
+        match (self.filter_lines_starting_with, line) {
+            (Some(prefix), Some(line)) => !line.starts_with(prefix),
+            _ => false,
+        }
+    }
+
+    /// Whether a character is whitespace, including the [additional
+    /// whitespace characters].
+    ///
+    /// [additional whitespace characters]: Self::whitespace_chars
+    fn is_blank(&self, character: char) -> bool {
+        character.is_whitespace() || self.whitespace_chars.contains(character)
+    }
+
+    /// Positions of the start of the first line with content and of the end
+    /// of the content, and the length of the text.
+    ///
+    /// Leading lines consisting only of whitespace aren't formatted unless
+    /// kept, along with the leading whitespace of the line after them, and
+    /// neither is trailing whitespace unless trailing blank lines are kept.
+    /// Diff markers aren't whitespace, and lines that are removed, for being
+    /// filtered out, having only removed images, or having only a stripped
+    /// heading marker, count as blank, as do lines with only flattened quote
+    /// markers.
+    fn content_bounds(&self) -> (usize, usize, usize) {
+        let mut start = None;
+        let mut end = 0;
+        let mut len = 0;
+        let mut fence = None;
+
+        for line in self.content().lines(self.unicode_newlines) {
+            let flattened = self.flattened_len(line.text);
+            let text = &line.text[flattened..];
+            let marker = text.chars().next().filter(|_| self.diff_mode);
+            let marker_len = marker.map_or(0, char::len_utf8);
+            let trimmed = self.content_len(&text[marker_len..], |character| {
+                self.is_blank(character) || self.is_removed(character)
+            });
+            let removed = self.is_filtered_out(Some(line.text))
+                || self.image_line(Some(line.text), fence)
+                || self.is_heading_marker(text, fence);
+            let content = match removed {
+                true => "",
+                false => &text[..marker_len + trimmed],
+            };
+            let line_fence = code_fence(line.text).map(|(fence, _, closing)| (fence, closing));
+            fence = Fence::next(fence, line_fence);
+
+            if !content.is_empty() {
+                // The first line keeps its indentation unless it follows
+                // skipped blank lines.
+                let indentation = match line.position {
+                    0 => 0,
+                    _ => line.text.len() - line.text.trim_start_matches([' ', TAB]).len(),
+                };
+
+                start = start.or(Some(line.position + indentation));
+                end = line.position + flattened + content.len();
+            }
+
+            len = line.end();
+        }
+
+        if self.keep_trailing_blank_lines {
+            end = len;
+        }
+
+        if !self.trim_leading_blank_lines {
+            start = Some(0);
+        }
+
+        (start.unwrap_or(len), end, len)
+    }
+
+    /// Position before the whitespace, including line breaks, preceding a
+    /// position the text is cut off at, if any.
+    fn whitespace_cut(&self, position: usize) -> Option<usize> {
+        let mut cut = None;
+
+        for line in self.content().lines(self.unicode_newlines) {
+            if line.position > position {
+                break;
+            }
+
+            let len = (position - line.position).min(line.text.len());
+            let text = line.text[..len].trim_end();
+
+            if !text.is_empty() {
+                cut = Some(line.position + text.len());
+            }
+        }
+
+        cut.filter(|cut| *cut < position)
+    }
+
+    /// Position of the end of the list item before the one containing a
+    /// position, if the position isn't in the first item of a list.
+    ///
+    /// A position at the end of a line is contained by the following line.
+    fn list_item_cut(&self, position: usize) -> Option<usize> {
+        let mut cut = None;
+        let mut in_list = false;
+        let mut blank = false;
+        let mut last_end = None;
+
+        for line in self.content().lines(self.unicode_newlines) {
+            if line.position > position && last_end != Some(position) {
+                break;
+            }
+
+            let content = line.text.trim_start();
+
+            if content.is_empty() {
+                blank = true;
+            } else {
+                if is_list_item(content) {
+                    cut = last_end.filter(|_| in_list);
+                    in_list = true;
+                } else if blank && content.len() == line.text.len() {
+                    // Paragraphs after blank lines end lists.
+                    cut = None;
+                    in_list = false;
+                }
+
+                blank = false;
+                last_end = Some(line.end());
+            }
+
+            if line.position > position {
+                break;
+            }
+        }
+
+        cut.filter(|cut| *cut < position)
+    }
+
+    /// Fence of the code block wrapping the text, if it's fenced.
+    ///
+    /// The fence is at least three backticks, and longer than any run of
+    /// backticks in the text.
+    fn fence(&self) -> Option<Fence> {
+        self.fenced?;
+
+        let mut len = 2;
+
+        for line in self.content().lines(self.unicode_newlines) {
+            let mut run = 0;
+
+            for character in line.text.chars() {
+                run = if character == '`' { run + 1 } else { 0 };
+                len = len.max(run);
+            }
+        }
+
+        Some(Fence {
+            character: '`',
+            len: len + 1,
+        })
+    }
+
+    /// Leading whitespace common to all lines with content, if dedenting.
+    fn common_indentation(&self) -> &'a str {
+        if !self.dedent || self.diff_mode {
+            return "";
+        }
+
+        let mut common: Option<&str> = None;
+
+        for line in self.content().lines(self.unicode_newlines) {
+            let line = line.text;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let indentation = &line[..line.len() - line.trim_start().len()];
+
+            common = Some(match common {
+                Some(common) => &common[..common_prefix_len(common, indentation)],
+                None => indentation,
+            });
+        }
+
+        common.unwrap_or_default()
+    }
+
+    /// Text a character is formatted as at a column.
+    ///
+    /// `leading` is the number of spaces preceding the character at the start
+    /// of its line, if only spaces precede it.
+    fn piece(&self, character: char, column: usize, leading: Option<usize>) -> Piece {
+        match character {
+            TAB => match self.tab_size {
+                Some(0) => Piece::Spaces(0),
+                Some(tab_size) => Piece::Spaces(tab_size - column % tab_size),
+                None => Piece::Char(character),
+            },
+            '>' if self.escape_existing_quotes && leading.is_some_and(|spaces| spaces <= 3) => {
+                Piece::Escaped(character)
+            }
+            _ => self.text_piece(character),
+        }
+    }
+
+    /// Whether a character of the text is removed, as a zero-width character
+    /// or control.
+    ///
+    /// Zero-width joiners between emoji are kept regardless.
+    fn is_removed(&self, character: char) -> bool {
+        self.strip_zero_width && is_strippable(character)
+            || self.sanitize_bidi == BidiPolicy::Strip && is_bidi_control(character)
+            || self.control_chars == ControlPolicy::Strip && is_control(character)
+    }
+
+    /// Piece formatted in place of a character of the text, which may be
+    /// escaped or replaced.
+    fn text_piece(&self, character: char) -> Piece {
+        match (character, self.backticks) {
+            _ if self.sanitize_bidi == BidiPolicy::Escape && is_bidi_control(character) => {
+                Piece::CodePoint(character)
+            }
+            _ if self.control_chars == ControlPolicy::Escape && is_control(character) => {
+                Piece::Control(character)
+            }
+            ('`', BacktickPolicy::Escape) => Piece::Escaped(character),
+            ('`', BacktickPolicy::Replace) => Piece::Char(GRAVE_LOOKALIKE),
+            _ if self
+                .escape
+                .is_some_and(|profile| profile.escapes(character)) =>
+            {
+                Piece::Escaped(character)
+            }
+            _ => Piece::Char(character),
+        }
+    }
+
+    /// Size of a piece in the limit unit.
+    fn limit_size(&self, piece: Piece) -> usize {
+        match (self.limit_unit, piece) {
+            (LimitUnit::Bytes, Piece::Char(NEWLINE)) => self.line_ending.as_str().len(),
+            (LimitUnit::Bytes, piece) => piece.len(),
+            (LimitUnit::Chars, piece) => piece.width(),
+            // Characters without a width, such as line breaks, are counted
+            // like characters.
+            #[cfg(feature = "unicode-width")]
+            (LimitUnit::Columns, Piece::Char(character)) => {
+                unicode_width::UnicodeWidthChar::width(character).unwrap_or(1)
+            }
+            #[cfg(feature = "unicode-width")]
+            (LimitUnit::Columns, piece) => piece.width(),
+            // Escapes are ASCII, so each of their characters is a cluster.
+            #[cfg(feature = "unicode-segmentation")]
+            (LimitUnit::Graphemes, piece) => piece.width(),
+        }
+    }
+
+    /// Size of text in the limit unit.
+    fn units(&self, text: &str) -> usize {
+        // Only the first character of a cluster counts.
+        #[cfg(feature = "unicode-segmentation")]
+        if self.limit_unit == LimitUnit::Graphemes {
+            return unicode_segmentation::UnicodeSegmentation::graphemes(text, true)
+                .filter_map(|cluster| cluster.chars().next())
+                .map(|character| self.limit_size(self.text_piece(character)))
+                .sum();
+        }
+
+        text.chars()
+            .map(|character| self.limit_size(self.text_piece(character)))
+            .sum()
+    }
+
+    /// Whether formatting `size` more units after `index` units exceeds the
+    /// limit.
+    fn reached_limit(&self, index: usize, size: usize, soft: bool) -> bool {
+        let limit = if soft {
+            self.soft_limit
+        } else {
+            let hard_limit = self.hard_limit.unwrap_or_default();
+
+            self.soft_limit.saturating_add(hard_limit)
+        };
+        let limit = self
+            .hard_limit_only
+            .map_or(limit, |hard_limit_only| limit.min(hard_limit_only));
+
+        index.saturating_add(size) > limit
+    }
+
+    /// Format the blockquote into a byte buffer, returning the number of bytes
+    /// written.
+    ///
+    /// This is useful on targets without an allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let mut buf = [0; 16];
+    /// let written = Blockquote::new("hello").write_bytes(&mut buf)?;
+    ///
+    /// assert_eq!(&buf[..written], b"> hello");
+    /// # Ok::<(), markdown_blockquote_formatter::BlockquoteError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockquoteError::Capacity`] if the buffer is too small to
+    /// hold the formatted blockquote. The buffer then holds as many whole
+    /// characters as fit.
+    pub fn write_bytes(&self, buf: &mut [u8]) -> Result<usize, BlockquoteError> {
+        let mut writer = ByteWriter { buf, written: 0 };
+
+        match write!(writer, "{}", self) {
+            Ok(()) => Ok(writer.written),
+            Err(_) => Err(BlockquoteError::Capacity(CapacityError {
+                written: writer.written,
+            })),
+        }
+    }
+
+    /// Format the blockquote into a writer, returning the number of bytes
+    /// written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let mut quote = String::from("they said:\n");
+    /// let written = Blockquote::new("hello").write_to(&mut quote)?;
+    ///
+    /// assert_eq!(written, 7);
+    /// assert_eq!(quote, "they said:\n> hello");
+    /// # Ok::<(), markdown_blockquote_formatter::BlockquoteError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockquoteError::Fmt`] if the writer fails.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<usize, BlockquoteError> {
+        let mut writer = CountingWriter {
+            inner: writer,
+            len: 0,
+        };
+
+        self.format(&mut writer)?;
+
+        Ok(writer.len)
+    }
+
+    /// Whether the text would be cut off with the given soft and hard limits.
+    ///
+    /// This is the same as checking whether the text is cut off after setting
+    /// the limits with [`soft_limit`] and [`hard_limit`]. Lines cut off only
+    /// by the [`per_line_limit`] don't count as the text being cut off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("this is a simple test");
+    ///
+    /// assert!(blockquote.would_truncate_at(18, None));
+    /// assert!(!blockquote.would_truncate_at(18, Some(3)));
+    /// ```
+    ///
+    /// [`hard_limit`]: Self::hard_limit
+    /// [`per_line_limit`]: Self::per_line_limit
+    /// [`soft_limit`]: Self::soft_limit
+    pub fn would_truncate_at(&self, soft: usize, hard: Option<usize>) -> bool {
+        let blockquote = Self {
+            hard_limit: hard,
+            soft_limit: soft,
+            ..*self
+        };
+
+        blockquote
+            .format(&mut Discard)
+            .is_ok_and(|formatted| formatted.truncated)
+    }
+
+    /// Number of characters of the text formatted before it's cut off.
+    ///
+    /// This is the number of characters of the text preceding the point it's
+    /// cut off at, excluding prefixes and the ellipsis, and is the number of
+    /// characters in the text if it isn't cut off. Lines cut off by the
+    /// [`per_line_limit`] don't affect the count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey, this is cool!");
+    ///
+    /// assert_eq!(blockquote.emitted_char_count(), 18);
+    /// assert_eq!(blockquote.soft_limit(9).emitted_char_count(), 9);
+    /// ```
+    ///
+    /// [`per_line_limit`]: Self::per_line_limit
+    pub fn emitted_char_count(&self) -> usize {
+        #[cfg(feature = "strip-markdown")]
+        if self.strip_markdown {
+            let text = strip::strip_markdown(self.content(), self.unicode_newlines);
+
+            return Blockquote {
+                source: Source::Text(&text),
+                strip_front_matter: false,
+                strip_markdown: false,
+                ..*self
+            }
+            .emitted_char_count();
+        }
+
+        let position = match self.format(&mut Discard) {
+            Ok(formatted) if formatted.truncated => formatted.position,
+            _ => usize::MAX,
+        };
+
+        self.content().char_count(position)
+    }
+
+    /// Number of lines of the formatted blockquote.
+    ///
+    /// Every line break written counts, including the one before an ellipsis
+    /// [on its own line], except for a [trailing newline], which doesn't start
+    /// another line. Nothing is formatted for [empty] blockquotes, so they
+    /// have no lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("first\nsecond\nthird").soft_limit(12);
+    ///
+    /// assert_eq!(blockquote.line_count(), 2);
+    /// assert_eq!(blockquote.ellipsis_on_new_line(true).line_count(), 3);
+    /// assert_eq!(Blockquote::new("").line_count(), 0);
+    /// ```
+    ///
+    /// [empty]: Self::is_empty
+    /// [on its own line]: Self::ellipsis_on_new_line
+    /// [trailing newline]: Self::trailing_newline
+    pub fn line_count(&self) -> usize {
+        let mut counter = LineCounter::default();
+
+        if self.format(&mut counter).is_err() {
+            return 0;
+        }
+
+        match counter.last {
+            None => 0,
+            Some(NEWLINE) => counter.breaks,
+            Some(_) => counter.breaks + 1,
+        }
+    }
+
+    /// Format each paragraph of the text as a separate blockquote.
+    ///
+    /// Paragraphs are separated by blank lines, and their blockquotes are too.
+    /// The limits cover the text of all of the paragraphs together, without
+    /// the blank lines between them, and the [maximum bytes] cover everything
+    /// formatted. Paragraphs after the one the text is cut off in aren't
+    /// formatted.
+    ///
+    /// Every paragraph is formatted with all of the options, so a header or
+    /// attribution is formatted in each blockquote.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let blockquote = Blockquote::new("hey,\nthis\n\nis cool!\n\nreally!").soft_limit(16);
+    ///
+    /// assert_eq!(
+    ///     blockquote.quote_each().to_string(),
+    ///     "> hey,\n> this\n\n> is cool…",
+    /// );
+    /// ```
+    ///
+    /// [maximum bytes]: Self::max_bytes
+    pub const fn quote_each(self) -> QuoteEach<'a> {
+        QuoteEach { blockquote: self }
+    }
+
+    /// Byte position in the text that it's cut off at, if it is.
+    ///
+    /// Text from this position on isn't formatted, so slicing the text at it
+    /// splits it into the formatted and hidden parts. The position is always
+    /// at a character boundary, including the position of skipped [front
+    /// matter]. For text [made of lines], the position is in the lines joined
+    /// by newlines. If markdown is stripped, the formatted text isn't a part
+    /// of the text, so there's no position and `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let text = "hey, this is cool!";
+    /// let blockquote = Blockquote::new(text);
+    ///
+    /// assert_eq!(blockquote.cut_byte_offset(), None);
+    ///
+    /// let offset = blockquote.soft_limit(9).cut_byte_offset();
+    ///
+    /// assert_eq!(offset, Some(9));
+    /// assert_eq!(&text[offset.unwrap()..], " is cool!");
+    /// ```
+    ///
+    /// [front matter]: Self::strip_front_matter
+    /// [made of lines]: Self::from_lines
+    pub fn cut_byte_offset(&self) -> Option<usize> {
+        #[cfg(feature = "strip-markdown")]
+        if self.strip_markdown {
+            return None;
+        }
+
+        let front_matter = self.source.len() - self.content().len();
+
+        self.format(&mut Discard)
+            .ok()
+            .filter(|formatted| formatted.truncated)
+            .map(|formatted| front_matter + formatted.position)
+    }
+
+    /// Format the blockquote once ahead of time, for rendering it repeatedly.
+    ///
+    /// The point the text is cut off at is found up front, including with the
+    /// [`max_bytes`], [`ellipsis_replaces`], and [`break_on`] options that
+    /// otherwise format the text more than once, so the prepared blockquote
+    /// renders in a single pass and knows whether it's truncated and how long
+    /// it is without formatting it again. If markdown is stripped, the prepared
+    /// blockquote keeps the stripped text rather than stripping it again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let prepared = Blockquote::new("hey, this is cool!").soft_limit(9).prepare();
+    ///
+    /// assert!(prepared.truncated());
+    /// assert_eq!(prepared.rendered_len(), "> hey, this…".len());
+    /// assert_eq!(prepared.to_string(), "> hey, this…");
+    /// ```
+    ///
+    /// [`break_on`]: Self::break_on
+    /// [`ellipsis_replaces`]: Self::ellipsis_replaces
+    /// [`max_bytes`]: Self::max_bytes
+    pub fn prepare(self) -> PreparedBlockquote<'a> {
+        // The stripped text is kept by the prepared blockquote, and quoted in
+        // place of the text when rendering.
+        #[cfg(feature = "strip-markdown")]
+        if self.strip_markdown {
+            let text = strip::strip_markdown(self.content(), self.unicode_newlines);
+            let stripped = Blockquote {
+                source: Source::Text(""),
+                strip_front_matter: false,
+                strip_markdown: false,
+                ..self
+            };
+            let prepared = Blockquote {
+                source: Source::Text(&text),
+                ..stripped
+            }
+            .prepare();
+            let blockquote = prepared
+                .blockquote
+                .map(|resolved| stripped.with_resolved(&resolved));
+            let (emitted_char_count, len, truncated) = (
+                prepared.emitted_char_count,
+                prepared.len,
+                prepared.truncated,
+            );
+
+            return PreparedBlockquote {
+                blockquote,
+                emitted_char_count,
+                len,
+                stripped: Some(text),
+                truncated,
+            };
+        }
+
+        let blockquote = self.resolve();
+        let mut counter = Counter::default();
+        let formatted = blockquote.map_or(
+            Ok(Formatted {
+                emphasis: false,
+                position: 0,
+                truncated: true,
+                units: 0,
+            }),
+            |blockquote| blockquote.format(&mut counter),
+        );
+        let truncated = formatted.is_ok_and(|formatted| formatted.truncated);
+
+        PreparedBlockquote {
+            blockquote,
+            emitted_char_count: self.emitted_char_count(),
+            len: counter.len,
+            #[cfg(feature = "strip-markdown")]
+            stripped: None,
+            truncated,
+        }
+    }
+
+    /// Whether the whitespace starting the rest of a line must be replaced by
+    /// a wrap, because the whitespace and the following word don't fit on the
+    /// current line.
+    fn wraps_at(&self, rest: &str, column: usize, width: usize) -> bool {
+        let mut chars = rest.chars().peekable();
+        let mut needed = column;
+
+        while let Some(character) = chars.next_if(|c| c.is_whitespace() && *c != NEWLINE) {
+            needed = needed.saturating_add(self.piece(character, needed, None).width());
+        }
+
+        let word = chars.take_while(|c| !c.is_whitespace());
+        needed = needed.saturating_add(word.map(|c| self.text_piece(c).width()).sum());
+
+        column > 0 && needed > width
+    }
+
+    /// Number of bytes at the start of a line that aren't formatted, and the
+    /// depth of the quote markers among them.
+    fn skipped_prefix(&self, line: &str, indentation: &str) -> (usize, usize) {
+        let dedent = common_prefix_len(indentation, line);
+
+        if !self.flatten_nested && !self.nest_existing {
+            return (dedent, 0);
+        }
+
+        let (markers, depth) = quote_markers(&line[dedent..], self.strip_ansi);
+
+        if self.flatten_nested {
+            return (dedent + markers, 0);
+        }
+
+        (dedent + markers, depth)
+    }
+
+    /// Number of bytes of the quote markers starting a line that are removed
+    /// by [flattening].
+    ///
+    /// [flattening]: Self::flatten_nested
+    fn flattened_len(&self, line: &str) -> usize {
+        match self.flatten_nested {
+            true => quote_markers(line, self.strip_ansi).0,
+            false => 0,
+        }
+    }
+
+    /// Code fence at the start of a line and the number of bytes up to the end
+    /// of its marker, if code fences are tracked.
+    fn line_fence(&self, line: &str) -> (Option<(Fence, bool)>, usize) {
+        if !self.balance_emphasis
+            && !self.close_code_fences
+            && !self.html_line_breaks
+            && !self.inline_markdown_aware
+            && !self.lazy
+            && self.images == ImagePolicy::Keep
+        {
+            return (None, 0);
+        }
+
+        code_fence(line).map_or((None, 0), |(fence, marker, closing)| {
+            (Some((fence, closing)), marker)
+        })
+    }
+
+    /// Table row starting a line, given the following line and whether the
+    /// previous line is in a table, if inline markdown is kept whole.
+    ///
+    /// Header rows include the delimiter row following them.
+    fn table_row(
+        &self,
+        line: &str,
+        next: Option<&str>,
+        indentation: &str,
+        table: bool,
+    ) -> Option<TableRow> {
+        if !self.inline_markdown_aware || line.trim().is_empty() {
+            return None;
+        }
+
+        // Tables continue until a blank line or the start of another block.
+        if table && (line.contains('|') || continues_paragraph(line)) {
+            return Some(TableRow {
+                header: false,
+                len: line.len(),
+                size: self.units(line),
+            });
+        }
+
+        let next = next?;
+        let (skip, _) = self.skipped_prefix(next, indentation);
+        let delimiter = &next[skip..];
+
+        if !line.contains('|') || !delimiter.contains('|') {
+            return None;
+        }
+
+        let mut header = table_cells(line);
+        let mut delimiters = table_cells(delimiter);
+
+        loop {
+            match (header.next(), delimiters.next()) {
+                (Some(_), Some(cell)) if is_delimiter_cell(cell) => {}
+                (None, None) => break,
+                _ => return None,
+            }
+        }
+
+        Some(TableRow {
+            header: true,
+            len: line.len() + NEWLINE.len_utf8() + delimiter.len(),
+            size: self.units(line) + self.limit_size(Piece::Char(NEWLINE)) + self.units(delimiter),
+        })
+    }
+
+    /// Whether a line has only images that are removed, outside of a code
+    /// block.
+    fn image_line(&self, line: Option<&str>, fence: Option<Fence>) -> bool {
+        match line {
+            Some(line) if self.images == ImagePolicy::Remove && fence.is_none() => {
+                only_images(line)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a line outside of code blocks has nothing but the marker of a
+    /// heading that's stripped.
+    fn is_heading_marker(&self, line: &str, fence: Option<Fence>) -> bool {
+        self.headings == HeadingPolicy::Strip
+            && fence.is_none()
+            && self
+                .heading(line, None, "", false)
+                .is_some_and(|heading| line[heading.skip..].trim().is_empty())
+    }
+
+    /// Heading starting a line, given the following line and whether the line
+    /// continues a paragraph, if headings are stripped or demoted.
+    ///
+    /// Setext headings spanning more than one line are left as is, so a line
+    /// continuing a paragraph is never a heading.
+    fn heading(
+        &self,
+        line: &str,
+        next: Option<&str>,
+        indentation: &str,
+        continued: bool,
+    ) -> Option<Heading> {
+        if self.headings == HeadingPolicy::Keep {
+            return None;
+        }
+
+        let content = line.trim_start_matches(' ');
+        let markers = content.trim_start_matches('#');
+        let level = content.len() - markers.len();
+
+        if line.len() - content.len() <= 3 && (1..=6).contains(&level) {
+            if !markers.is_empty() && !markers.starts_with([' ', TAB]) {
+                return None;
+            }
+
+            let mut skip = line.len() - markers.len();
+
+            if self.headings == HeadingPolicy::Strip {
+                skip = line.len() - markers.trim_start().len();
+            }
+
+            return Some(Heading {
+                level,
+                setext: false,
+                skip,
+            });
+        }
+
+        if continued
+            || line.trim().is_empty()
+            || is_list_item(line.trim_start())
+            || code_fence(line).is_some()
+        {
+            return None;
+        }
+
+        let next = next?;
+        let (next_skip, _) = self.skipped_prefix(next, indentation);
+        let underline = next[next_skip..].trim_end();
+        let characters = underline.trim_start_matches(' ');
+
+        let level = match characters.chars().next() {
+            Some('=') => 1,
+            Some('-') => 2,
+            _ => return None,
+        };
+
+        let character = if level == 1 { '=' } else { '-' };
+
+        if underline.len() - characters.len() > 3
+            || !characters.trim_start_matches(character).is_empty()
+        {
+            return None;
+        }
+
+        Some(Heading {
+            level,
+            setext: true,
+            skip: 0,
+        })
+    }
+
+    /// Write content of a line, opening the line's content if it's the first
+    /// content written on the line.
+    ///
+    /// Strikethrough is opened at the first content that isn't whitespace.
+    fn write_content<W: Write>(
+        &self,
+        f: &mut W,
+        content: &mut Content,
+        piece: Piece,
+    ) -> Result<(), FmtError> {
+        if !content.open {
+            content.open = true;
+
+            if self.bidi_isolate {
+                write_char(FIRST_STRONG_ISOLATE, f)?;
+            }
+        }
+
+        if let (BidiPolicy::Close, Piece::Char(character)) = (self.sanitize_bidi, piece) {
+            content.directional.update(character);
+        }
+
+        if self.strikethrough && !content.struck && !piece.is_whitespace() {
+            content.struck = true;
+
+            f.write_str(STRIKETHROUGH)?;
+        }
+
+        piece.write(f)
+    }
+
+    /// Close the strikethrough of the current line, if opened.
+    fn close_strikethrough<W: Write>(
+        &self,
+        f: &mut W,
+        content: &mut Content,
+    ) -> Result<(), FmtError> {
+        if !content.struck {
+            return Ok(());
+        }
+
+        content.struck = false;
+
+        f.write_str(STRIKETHROUGH)
+    }
+
+    /// Close the content of the current line, if any content was written.
+    fn close_line<W: Write>(&self, f: &mut W, content: &mut Content) -> Result<(), FmtError> {
+        while let Some(closing) = content.directional.pop() {
+            write_char(closing, f)?;
+        }
+
+        self.close_strikethrough(f, content)?;
+
+        if !content.open {
+            return Ok(());
+        }
+
+        content.open = false;
+
+        if self.bidi_isolate {
+            write_char(POP_DIRECTIONAL_ISOLATE, f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a line break, closing the content of the current line.
+    fn write_line_break<W: Write>(&self, f: &mut W, content: &mut Content) -> Result<(), FmtError> {
+        self.close_line(f, content)?;
+
+        f.write_str(self.line_ending.as_str())
+    }
+
+    /// Width of the line numbers, if lines are numbered, given the end of the
+    /// content.
+    ///
+    /// Lines after the end of the content aren't formatted, so they don't
+    /// widen the gutter.
+    fn gutter(&self, end: usize) -> Option<usize> {
+        let start = self.line_numbers?;
+        let lines = self
+            .content()
+            .lines(self.unicode_newlines)
+            .take_while(|line| line.position <= end)
+            .count();
+
+        Some(decimal_len(start.saturating_add(lines.saturating_sub(1))))
+    }
+
+    /// Write the gutter of a line, with its number if it isn't continuing a
+    /// wrapped line.
+    fn write_gutter<W: Write>(
+        &self,
+        f: &mut W,
+        gutter: Option<usize>,
+        number: Option<usize>,
+    ) -> Result<(), FmtError> {
+        match (gutter, number) {
+            (Some(width), Some(number)) => write!(f, "{:>width$} | ", number, width = width),
+            (Some(width), None) => write!(f, "{:width$} | ", "", width = width),
+            (None, _) => Ok(()),
+        }
+    }
+
+    /// Write the prefix of a line produced by wrapping, nested within quotes of
+    /// a depth.
+    fn write_continuation_prefix<W: Write>(&self, f: &mut W, depth: usize) -> Result<(), FmtError> {
+        match self.wrap_continuation {
+            Some(_) if self.render != Format::Markdown => Ok(()),
+            Some(wrap_continuation) => f.write_str(wrap_continuation),
+            None => self.write_prefix(f, &mut false, depth),
+        }
+    }
+
+    /// Write the prefix of a line nested within quotes of a depth.
+    ///
+    /// The first line's prefix may be overridden, and other lines may not be
+    /// prefixed at all.
+    fn write_prefix<W: Write>(
+        &self,
+        f: &mut W,
+        first: &mut bool,
+        depth: usize,
+    ) -> Result<(), FmtError> {
+        let first = mem::take(first);
+
+        // Lines are wrapped by an element in HTML, and indented as they're
+        // written in reStructuredText.
+        if self.render != Format::Markdown {
+            return Ok(());
+        }
+
+        if first {
+            if let Some(first_line_prefix) = self.first_line_prefix {
+                return f.write_str(first_line_prefix);
+            }
+        } else if self.prefix_mode == PrefixMode::FirstOnly {
+            return Ok(());
+        }
+
+        f.write_str(self.indent)?;
+
+        match self.nesting_style {
+            NestingStyle::Contiguous => {
+                for _ in 0..depth {
+                    f.write_str(">")?;
+                }
+
+                f.write_str(Self::DEFAULT_PREFIX)
+            }
+            NestingStyle::Spaced => {
+                for _ in 0..=depth {
+                    f.write_str(Self::DEFAULT_PREFIX)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Blockquote<'_> {
+    /// Format the blockquote into a writer.
+    fn format<W: Write>(&self, f: &mut W) -> Result<Formatted, FmtError> {
+        #[cfg(feature = "strip-markdown")]
+        if self.strip_markdown {
+            let text = strip::strip_markdown(self.content(), self.unicode_newlines);
+
+            return Blockquote {
+                source: Source::Text(&text),
+                strip_front_matter: false,
+                strip_markdown: false,
+                ..*self
+            }
+            .format(f);
+        }
+
+        match self.resolve() {
+            Some(blockquote) => blockquote.write_resolved(f),
+            None => Ok(Formatted {
+                emphasis: false,
+                position: 0,
+                truncated: true,
+                units: 0,
+            }),
+        }
+    }
+
+    /// Blockquote formatted in a single pass with the same result, without
+    /// the options that need formatting to be tried first, if anything fits.
+    ///
+    /// The minimum characters, maximum bytes, reserving room for the
+    /// ellipsis, and cutting at list items are resolved into limits and cut
+    /// positions.
+    fn resolve(&self) -> Option<Self> {
+        // Format nothing or everything if too little of the text is formatted.
+        if let Some(min_chars) = self.min_chars {
+            let blockquote = Self {
+                min_chars: None,
+                ..*self
+            };
+            let short = blockquote.format(&mut Discard).is_ok_and(|formatted| {
+                formatted.truncated && self.content().char_count(formatted.position) < min_chars
+            });
+
+            return match (short, self.min_chars_policy) {
+                (false, _) => blockquote.resolve(),
+                (true, MinCharsPolicy::Empty) => None,
+                (true, MinCharsPolicy::Unlimited) => Self {
+                    hard_limit: None,
+                    hard_limit_only: None,
+                    max_bytes: None,
+                    soft_limit: usize::MAX,
+                    ..blockquote
+                }
+                .resolve(),
+            };
+        }
+
+        // Cut the text at the most units such that the blockquote fits.
+        if let Some(max_bytes) = self.max_bytes {
+            let fitting = |units: Option<usize>| {
+                let blockquote = Self {
+                    hard_limit_only: match (units, self.hard_limit_only) {
+                        (Some(units), Some(hard_limit_only)) => Some(units.min(hard_limit_only)),
+                        (units, hard_limit_only) => units.or(hard_limit_only),
+                    },
+                    max_bytes: None,
+                    ..*self
+                };
+                let mut counter = Counter::default();
+
+                blockquote
+                    .format(&mut counter)
+                    .ok()
+                    .filter(|_| counter.len <= max_bytes)
+                    .map(|_| blockquote)
+            };
+
+            if let Some(blockquote) = fitting(None) {
+                return blockquote.resolve();
+            }
+
+            // Each unit is at least one byte, so no more units than bytes fit.
+            let mut low = 0;
+            let mut high = max_bytes;
+
+            while low < high {
+                let middle = low + (high - low + 1) / 2;
+
+                if fitting(Some(middle)).is_some() {
+                    low = middle;
+                } else {
+                    high = middle - 1;
+                }
+            }
+
+            return fitting(Some(low)).and_then(|blockquote| blockquote.resolve());
+        }
+
+        // Reserve room for the ellipsis if the text is truncated.
+        if self.ellipsis_replaces && self.with_ellipsis {
+            let blockquote = Self {
+                ellipsis_replaces: false,
+                ..*self
+            };
+
+            let truncated = blockquote
+                .format(&mut Discard)
+                .is_ok_and(|formatted| formatted.truncated);
+
+            if !truncated {
+                return blockquote.resolve();
+            }
+
+            let suffix_size = self.units(self.truncation_suffix);
+
+            let limit = self
+                .hard_limit_only
+                .map_or(self.soft_limit, |hard_limit_only| {
+                    self.soft_limit.min(hard_limit_only)
+                });
+
+            let reserved = Self {
+                hard_limit_only: self
+                    .hard_limit_only
+                    .map(|hard_limit_only| hard_limit_only.saturating_sub(suffix_size)),
+                soft_limit: self.soft_limit.saturating_sub(suffix_size),
+                with_ellipsis: suffix_size <= limit,
+                ..blockquote
+            };
+
+            return reserved.resolve();
+        }
+
+        // Cut the text at the end of the last list item that fits.
+        if self.break_on == Some(Boundary::ListItem) && self.cut.is_none() {
+            let blockquote = Self {
+                break_on: None,
+                ..*self
+            };
+            let cut = blockquote
+                .format(&mut Discard)
+                .ok()
+                .filter(|formatted| formatted.truncated)
+                .and_then(|formatted| self.list_item_cut(formatted.position));
+
+            return Self { cut, ..blockquote }.resolve();
+        }
+
+        // Cut the text before whitespace it's cut off after, as emphasis
+        // can't be closed after whitespace.
+        if self.balance_emphasis && self.cut.is_none() {
+            let cut = self
+                .write_resolved(&mut Discard)
+                .ok()
+                .filter(|formatted| formatted.emphasis)
+                .and_then(|formatted| self.whitespace_cut(formatted.position));
+
+            return Some(Self { cut, ..*self });
+        }
+
+        Some(*self)
+    }
+
+    /// Blockquote with the limits, ellipsis, and cut position of a blockquote
+    /// resolved from it, which may be quoting other text.
+    ///
+    /// These are the only options changed by resolving.
+    #[cfg(feature = "strip-markdown")]
+    const fn with_resolved(self, resolved: &Blockquote<'_>) -> Self {
+        Self {
+            break_on: resolved.break_on,
+            cut: resolved.cut,
+            ellipsis_replaces: resolved.ellipsis_replaces,
+            hard_limit: resolved.hard_limit,
+            hard_limit_only: resolved.hard_limit_only,
+            max_bytes: resolved.max_bytes,
+            min_chars: resolved.min_chars,
+            soft_limit: resolved.soft_limit,
+            with_ellipsis: resolved.with_ellipsis,
+            ..self
+        }
+    }
+
+    /// Format the blockquote into a writer in a single pass, in the format
+    /// it's rendered in.
+    fn write_resolved<W: Write>(&self, f: &mut W) -> Result<Formatted, FmtError> {
+        match self.render {
+            // Nothing is formatted for empty text, not even the delimiters.
+            Format::AsciiDoc
+            | Format::DiscordMultiline
+            | Format::Jira
+            | Format::Org
+            | Format::Textile
+                if self.is_blank_text() =>
+            {
+                self.write_lines(f)
+            }
+            Format::AsciiDoc => {
+                if self.leading_newline {
+                    f.write_str(self.line_ending.as_str())?;
+                }
+
+                f.write_str("[quote")?;
+
+                if let Some(author) = self.attribution {
+                    f.write_str(", ")?;
+
+                    // Commas would separate the author into more attributes.
+                    if author.contains(',') {
+                        f.write_str("\"")?;
+
+                        for character in author.chars() {
+                            if character == '"' {
+                                f.write_str("\\")?;
+                            }
+
+                            f.write_char(character)?;
+                        }
+
+                        f.write_str("\"")?;
+                    } else {
+                        f.write_str(author)?;
+                    }
+                }
+
+                f.write_str("]")?;
+                f.write_str(self.line_ending.as_str())?;
+
+                Self {
+                    attribution: None,
+                    leading_newline: false,
+                    ..*self
+                }
+                .write_block(f, "____", "____")
+            }
+            Format::DiscordMultiline => {
+                if self.leading_newline {
+                    f.write_str(self.line_ending.as_str())?;
+                }
+
+                f.write_str(">>> ")?;
+
+                Self {
+                    attribution: None,
+                    leading_newline: false,
+                    ..*self
+                }
+                .write_lines(f)
+            }
+            Format::Html => {
+                let mut html = HtmlWriter {
+                    breaks: 0,
+                    inner: f,
+                    open: false,
+                };
+                let formatted = self.write_lines(&mut html)?;
+                html.finish()?;
+
+                Ok(formatted)
+            }
+            Format::Jira => self.write_block(f, "{quote}", "{quote}"),
+            Format::Markdown => self.write_lines(f),
+            Format::Org => self.write_block(f, "#+BEGIN_QUOTE", "#+END_QUOTE"),
+            Format::Plain => self.write_lines(&mut IndentWriter {
+                count: 1,
+                indent: self.plain_indent,
+                inner: f,
+                line_start: true,
+            }),
+            Format::Rst => self.write_lines(&mut IndentWriter {
+                count: self.rst_indent,
+                indent: " ",
+                inner: f,
+                line_start: true,
+            }),
+            Format::Textile => {
+                let line_ending = self.line_ending.as_str();
+                let blockquote = Self {
+                    leading_newline: false,
+                    trailing_newline: false,
+                    ..*self
+                };
+
+                // Quotes of more than one paragraph are extended blocks.
+                let mut blank_lines = BlankLineFinder::default();
+                blockquote.write_lines(&mut blank_lines)?;
+                let extended = blank_lines.found;
+
+                if self.leading_newline {
+                    f.write_str(line_ending)?;
+                }
+
+                f.write_str(if extended { "bq.. " } else { "bq. " })?;
+
+                let formatted = blockquote.write_lines(f)?;
+
+                // Extended blocks end at the start of another block.
+                if extended {
+                    f.write_str(line_ending)?;
+                    f.write_str(line_ending)?;
+                    f.write_str("p.")?;
+                }
+
+                if self.trailing_newline {
+                    f.write_str(line_ending)?;
+                }
+
+                Ok(formatted)
+            }
+        }
+    }
+
+    /// Format the lines of the blockquote between lines opening and closing
+    /// a block.
+    fn write_block<W: Write>(
+        &self,
+        f: &mut W,
+        opening: &str,
+        closing: &str,
+    ) -> Result<Formatted, FmtError> {
+        let line_ending = self.line_ending.as_str();
+
+        if self.leading_newline {
+            f.write_str(line_ending)?;
+        }
+
+        f.write_str(opening)?;
+        f.write_str(line_ending)?;
+
+        let blockquote = Self {
+            leading_newline: false,
+            trailing_newline: false,
+            ..*self
+        };
+
+        // Braces are escaped so the text can't start or end macros, and
+        // lines starting with the closing delimiter so they can't close the
+        // block.
+        let formatted = match self.render {
+            Format::AsciiDoc => {
+                let mut escaper = DelimiterEscaper::new(f, closing, "{empty}");
+                let formatted = blockquote.write_lines(&mut escaper)?;
+                escaper.finish()?;
+
+                formatted
+            }
+            Format::Jira => blockquote.write_lines(&mut BraceEscaper { inner: f })?,
+            Format::Org => {
+                let mut escaper = DelimiterEscaper::new(f, closing, ",");
+                let formatted = blockquote.write_lines(&mut escaper)?;
+                escaper.finish()?;
+
+                formatted
+            }
+            _ => blockquote.write_lines(f)?,
+        };
+
+        f.write_str(line_ending)?;
+        f.write_str(closing)?;
+
+        if self.trailing_newline {
+            f.write_str(line_ending)?;
+        }
+
+        Ok(formatted)
+    }
+
+    /// Format the lines of the blockquote into a writer in a single pass.
+    fn write_lines<W: Write>(&self, f: &mut W) -> Result<Formatted, FmtError> {
+        #[derive(Clone, Copy, Eq, PartialEq)]
+        enum Stage {
+            Ongoing,
+            StartLine,
+            Wrapping,
+        }
+
+        if self.is_blank_text() {
+            return Ok(Formatted {
+                emphasis: false,
+                position: 0,
+                truncated: false,
+                units: 0,
+            });
+        }
+
+        if self.leading_newline {
+            f.write_str(self.line_ending.as_str())?;
+        }
+
+        let mut first = true;
+
+        if let Some(alert) = self.alert {
+            self.write_prefix(f, &mut first, 0)?;
+            f.write_str("[!")?;
+
+            if self.normalize_alert_case {
+                for character in alert.as_str().chars() {
+                    write_char(character.to_ascii_uppercase(), f)?;
+                }
+            } else {
+                f.write_str(alert.as_str())?;
+            }
+
+            f.write_str("]")?;
+            f.write_str(self.line_ending.as_str())?;
+        }
+
+        if let Some(header) = self.header {
+            self.write_prefix(f, &mut first, 0)?;
+            f.write_str(header)?;
+            f.write_str(self.line_ending.as_str())?;
+        }
+
+        if let Some(summary) = self.collapsible {
+            self.write_prefix(f, &mut first, 0)?;
+            f.write_str("<details><summary>")?;
+            f.write_str(summary)?;
+            f.write_str("</summary>")?;
+            f.write_str(self.line_ending.as_str())?;
+            self.write_prefix(f, &mut first, 0)?;
+            f.write_str(self.empty_line_fill)?;
+            f.write_str(self.line_ending.as_str())?;
+        }
+
+        // The text is formatted as if it's already in the code block.
+        let mut fence = self.fence();
+
+        if let (Some(fence), Some(language)) = (fence, self.fenced) {
+            self.write_prefix(f, &mut first, 0)?;
+
+            for _ in 0..fence.len {
+                write_char(fence.character, f)?;
+            }
+
+            f.write_str(language)?;
+            f.write_str(self.line_ending.as_str())?;
+        }
+
+        let (start, end, len) = self.content_bounds();
+        let indentation = self.common_indentation();
+        let gutter = self.gutter(end);
+        let mut line_number = self.line_numbers.unwrap_or_default();
+        let mut column = 0;
+        let mut depth = 0;
+        let mut line_fence = None;
+        // Length of the code fence marker starting the next line, including
+        // the prefix skipped before it.
+        let mut marker = None;
+        // Position of the end of the code fence marker starting the line.
+        let mut marker_end = 0;
+        let mut url = 0;
+        let mut span = 0;
+        let mut code = 0;
+        let mut emphasis = Emphasis::default();
+        let mut table = false;
+        let mut row = None;
+        let mut heading = None;
+        // Whether the last line started was paragraph text.
+        let mut text_line = false;
+        let mut image = None;
+        let mut dropped = false;
+        let mut last = NEWLINE;
+        let mut word_start = true;
+        let mut paragraph = false;
+        let mut bare = false;
+        // Number of lines of the text started, for cycling through prefixes.
+        let mut line_count: usize = 0;
+        let mut skip = 0;
+        let mut index = 0;
+        let mut line_index: usize = 0;
+        let mut leading = Some(0);
+        // Whether only spaces and tabs have been formatted on the line.
+        let mut indenting = true;
+        let mut line_truncated = false;
+        let mut timestamped = false;
+        // Position of the end of an alert marker written in uppercase.
+        let mut alert_end = 0;
+        // Position of the end of the grapheme cluster being formatted.
+        #[cfg(feature = "unicode-segmentation")]
+        let mut cluster_end = 0;
+        let mut content = Content::default();
+        let mut position = len;
+        let mut stage = Stage::StartLine;
+
+        let mut chars = self.content().chars(self.unicode_newlines);
+
+        while let Some((byte, character, rest)) = chars.next() {
+            // Skip the blank lines before the first line with content.
+            if byte < start {
+                // Lines are numbered by their position in the text.
+                if character == NEWLINE {
+                    line_number = line_number.saturating_add(1);
+                }
+
+                continue;
+            }
+
+            // The rest of an empty first line is the following line.
+            if byte == start && character != NEWLINE {
+                (skip, depth) = self.skipped_prefix(rest, indentation);
+                let len;
+                (line_fence, len) = self.line_fence(&rest[skip..]);
+                marker = Some(skip + len).filter(|_| len > 0);
+                paragraph = self.lazy && fence.is_none() && continues_paragraph(&rest[skip..]);
+                row = fence
+                    .is_none()
+                    .then(|| self.table_row(&rest[skip..], chars.next_line(), indentation, table))
+                    .flatten();
+                table = row.is_some();
+                heading = fence
+                    .is_none()
+                    .then(|| {
+                        let line = &rest[skip..];
+
+                        self.heading(line, chars.next_line(), indentation, text_line)
+                    })
+                    .flatten();
+                text_line =
+                    fence.is_none() && heading.is_none() && continues_paragraph(&rest[skip..]);
+                skip += heading.map_or(0, |heading| heading.skip);
+                dropped = heading.is_some_and(|heading| heading.setext)
+                    || self.image_line(chars.next_line(), Fence::next(fence, line_fence))
+                    || self.is_filtered_out(chars.next_line());
+
+                if self.normalize_alert_case {
+                    alert_end = alert_marker_len(&rest[skip..]).map_or(0, |len| byte + skip + len);
+                }
+            }
+
+            if let Some(len) = marker.take() {
+                marker_end = byte + len;
+            }
+
+            // Stop if all the remaining text is whitespace.
+            if byte >= end {
+                position = byte;
+
+                break;
+            }
+
+            if self.cut.is_some_and(|cut| byte >= cut) {
+                position = byte;
+
+                break;
+            }
+
+            // The destination of an image replaced by its alt text is skipped.
+            if let Some((tail, len)) = image {
+                if byte == tail {
+                    skip = len;
+                    image = None;
+                }
+            }
+
+            // Skip the indentation common to all lines and quote markers, and
+            // the rest of lines cut off by the per-line limit.
+            if skip > 0 {
+                skip -= character.len_utf8();
+
+                continue;
+            }
+
+            if line_truncated && character != NEWLINE {
+                continue;
+            }
+
+            // Zero-width characters and bidirectional controls are removed,
+            // unless they join emoji.
+            if self.is_removed(character)
+                && !(character == ZERO_WIDTH_JOINER
+                    && is_emoji(last)
+                    && rest[character.len_utf8()..]
+                        .chars()
+                        .next()
+                        .is_some_and(is_emoji))
+            {
+                continue;
+            }
+
+            // ANSI escape sequences are removed.
+            if self.strip_ansi && character == ESCAPE {
+                if let Some(len) = ansi_len(rest) {
+                    skip = len - character.len_utf8();
+
+                    continue;
+                }
+            }
+
+            // Runs of whitespace after the indentation are squashed.
+            let squashed = self.squash_whitespace && !indenting && matches!(character, ' ' | TAB);
+
+            if squashed && matches!(last, ' ' | TAB) {
+                continue;
+            }
+
+            // The underline of a setext heading, lines with only an image, and
+            // filtered out lines are skipped along with the line break before
+            // them.
+            if character == NEWLINE && mem::take(&mut dropped) {
+                skip = rest.len();
+                line_number = line_number.saturating_add(1);
+                dropped = self.image_line(chars.next_line(), Fence::next(fence, line_fence))
+                    || self.is_filtered_out(chars.next_line());
+
+                continue;
+            }
+
+            // Whitespace replaced by a wrap is consumed until the next word.
+            if stage == Stage::Wrapping {
+                if character.is_whitespace() && character != NEWLINE {
+                    let size = self.limit_size(Piece::Char(character));
+
+                    if self.reached_limit(index, size, true) {
+                        position = byte;
+
+                        break;
+                    }
+
+                    index += size;
+
+                    continue;
+                }
+
+                // Whitespace at the end of a line is consumed without a wrap.
+                if character != NEWLINE {
+                    self.write_line_break(f, &mut content)?;
+
+                    if !paragraph {
+                        self.write_continuation_prefix(f, depth)?;
+                    }
+
+                    self.write_gutter(f, gutter, None)?;
+
+                    column = 0;
+                }
+
+                stage = Stage::Ongoing;
+            }
+
+            if stage == Stage::StartLine {
+                let alternating = self
+                    .alternating_prefixes
+                    .get(line_count % self.alternating_prefixes.len().max(1));
+
+                match alternating {
+                    _ if bare || self.render != Format::Markdown => {}
+                    Some(prefix) => {
+                        first = false;
+                        f.write_str(prefix)?;
+                    }
+                    None => self.write_prefix(f, &mut first, depth)?,
+                }
+
+                line_count += 1;
+
+                self.write_gutter(f, gutter, Some(line_number))?;
+
+                if character != NEWLINE {
+                    if let Some(timestamp) = self.timestamp {
+                        if self.timestamp_every_line || !timestamped {
+                            f.write_str(timestamp)?;
+
+                            if !timestamp.ends_with(char::is_whitespace) {
+                                f.write_str(" ")?;
+                            }
+
+                            timestamped = true;
+                        }
+                    }
+
+                    if let (HeadingPolicy::Demote(levels), Some(heading)) =
+                        (self.headings, heading.take())
+                    {
+                        for _ in 0..heading.level.saturating_add(levels).min(6) {
+                            f.write_str("#")?;
+                        }
+
+                        if heading.setext {
+                            f.write_str(" ")?;
+                        }
+                    }
+
+                    stage = Stage::Ongoing;
+                }
+            }
+
+            // Table rows are never split, so text is cut before rows that
+            // don't fit, and before entire tables if their header doesn't.
+            if let Some(TableRow { header, len, size }) = row.take() {
+                if self.reached_limit(index, size, false) {
+                    table = table && !header;
+                    position = byte;
+
+                    break;
+                }
+
+                span = len;
+            }
+
+            // Whitespace before images removed up to the end of the line would
+            // be left trailing, so the rest of the line is skipped.
+            if self.images == ImagePolicy::Remove
+                && fence.is_none()
+                && code == 0
+                && matches!(character, ' ' | TAB)
+                && only_images(rest)
+            {
+                skip = rest.len() - character.len_utf8();
+
+                continue;
+            }
+
+            if self.images != ImagePolicy::Keep && character == '!' && fence.is_none() && code == 0
+            {
+                if let Some((len, alt)) = image_len(rest) {
+                    if self.images == ImagePolicy::Remove {
+                        // Whitespace on both sides of the image would double.
+                        let after = &rest[len..];
+                        let whitespace = after.len() - after.trim_start_matches([' ', TAB]).len();
+                        skip = len - character.len_utf8();
+                        skip += if last.is_whitespace() { whitespace } else { 0 };
+
+                        continue;
+                    }
+
+                    if alt > 0 {
+                        // Skip the opening of the image, write the alt text,
+                        // and then skip the rest.
+                        skip = "[".len();
+                        image = Some((byte + "![".len() + alt, len - "![".len() - alt));
+
+                        continue;
+                    }
+
+                    let size = self.units(IMAGE_PLACEHOLDER);
+
+                    if self.reached_limit(index, size, false) {
+                        position = byte;
+
+                        break;
+                    }
+
+                    for character in IMAGE_PLACEHOLDER.chars() {
+                        self.write_content(f, &mut content, Piece::Char(character))?;
+                    }
+
+                    skip = len - character.len_utf8();
+                    index += size;
+                    line_index += size;
+                    column += IMAGE_PLACEHOLDER.len();
+                    last = ']';
+                    word_start = false;
+
+                    continue;
+                }
+            }
+
+            // Trailing whitespace is trimmed, except for the two spaces of a
+            // hard line break and diff markers.
+            if self.preserve_hard_breaks
+                && !(self.diff_mode && line_index == 0)
+                && character != NEWLINE
+                && character.is_whitespace()
+                && rest.trim_end().is_empty()
+            {
+                skip = rest.len() - character.len_utf8();
+
+                if column == 0 || !rest.ends_with("  ") {
+                    continue;
+                }
+
+                let piece = Piece::Spaces(2);
+                let size = self.limit_size(piece);
+
+                if self.reached_limit(index, size, true) {
+                    position = byte;
+
+                    break;
+                }
+
+                self.close_strikethrough(f, &mut content)?;
+                self.write_content(f, &mut content, piece)?;
+                index += size;
+                line_index += size;
+                column += piece.width();
+
+                continue;
+            }
+
+            // Tabs indenting a line are expanded in markdown, since the quote
+            // marker moves their tab stops, changing how indented the line is.
+            let piece = if squashed {
+                Piece::Char(' ')
+            } else if character == TAB
+                && indenting
+                && self.tab_size.is_none()
+                && self.render == Format::Markdown
+            {
+                Piece::Spaces(TAB_STOP - column % TAB_STOP)
+            } else if byte < alert_end {
+                self.piece(character.to_ascii_uppercase(), column, leading)
+            } else {
+                self.piece(character, column, leading)
+            };
+            let width = piece.width();
+
+            // Tabs may be removed entirely.
+            if width == 0 {
+                continue;
+            }
+
+            let size = self.limit_size(piece);
+
+            // Characters continuing a grapheme cluster don't count.
+            #[cfg(feature = "unicode-segmentation")]
+            let size = if self.limit_unit != LimitUnit::Graphemes || character == NEWLINE {
+                size
+            } else if byte < cluster_end {
+                0
+            } else {
+                let cluster = unicode_segmentation::UnicodeSegmentation::graphemes(rest, true)
+                    .next()
+                    .unwrap_or_default();
+                cluster_end = byte + cluster.len();
+
+                size
+            };
+
+            if self.reached_limit(index, size, character.is_whitespace() && span == 0) {
+                position = byte;
+
+                break;
+            }
+
+            // URLs are never split, so text is cut before URLs that don't fit,
+            // along with the whitespace preceding them.
+            if url == 0 && character != NEWLINE && (word_start || character.is_whitespace()) {
+                let word = rest.trim_start();
+
+                if let Some(len) = url_len(word) {
+                    let whitespace = &rest[..rest.len() - word.len()];
+                    let url_size = self.units(whitespace) + self.units(&word[..len]);
+
+                    if self.reached_limit(index, url_size, false) {
+                        position = byte;
+
+                        break;
+                    }
+
+                    url = rest.len() - word.len() + len;
+                }
+            }
+
+            // Code fence markers are cut before entirely when fences are
+            // closed, rather than leaving part of the marker behind.
+            if byte < marker_end
+                && (self.close_code_fences || self.inline_markdown_aware)
+                && self.reached_limit(index, self.units(&rest[..marker_end - byte]), false)
+            {
+                position = byte;
+
+                break;
+            }
+
+            // Likewise, inline markdown is cut before entirely if enabled.
+            if self.inline_markdown_aware && span == 0 && fence.is_none() && character != NEWLINE {
+                let token = if character.is_whitespace() {
+                    rest.trim_start()
+                } else {
+                    rest
+                };
+
+                if let Some(len) = inline_markdown_len(token) {
+                    let whitespace = &rest[..rest.len() - token.len()];
+                    let span_size = self.units(whitespace) + self.units(&token[..len]);
+
+                    if self.reached_limit(index, span_size, false) {
+                        position = byte;
+
+                        break;
+                    }
+
+                    span = rest.len() - token.len() + len;
+                } else if token.starts_with('`') {
+                    // Unmatched backticks are literal, including the rest of
+                    // their run.
+                    span = rest.len() - token.trim_start_matches('`').len();
+                }
+            }
+
+            if let Some(per_line_limit) = self.per_line_limit {
+                if character != NEWLINE && line_index.saturating_add(size) > per_line_limit {
+                    if self.with_ellipsis && !rest.trim_end().is_empty() {
+                        f.write_str(self.truncation_suffix)?;
+                    }
+
+                    line_truncated = true;
+
+                    continue;
+                }
+            }
+
+            if let Some(width) = self.wrap {
+                let width = width.saturating_sub(gutter.map_or(0, |gutter| gutter + 3));
+
+                if character.is_whitespace() && character != NEWLINE {
+                    if self.wraps_at(rest, column, width) {
+                        stage = Stage::Wrapping;
+                        index += size;
+                        url = url.saturating_sub(character.len_utf8());
+                        span = span.saturating_sub(character.len_utf8());
+                        word_start = true;
+
+                        continue;
+                    }
+                } else if character != NEWLINE && column >= width && url == 0 {
+                    // Words longer than the wrap width are broken, but URLs
+                    // are kept whole on their own lines.
+                    self.write_line_break(f, &mut content)?;
+
+                    if !paragraph {
+                        self.write_continuation_prefix(f, depth)?;
+                    }
+
+                    self.write_gutter(f, gutter, None)?;
+
+                    column = 0;
+                }
+            }
+
+            if (self.balance_emphasis || self.images != ImagePolicy::Keep) && fence.is_none() {
+                if character == '`' && last != '`' && code == 0 {
+                    code = code_span_len(rest).unwrap_or_default();
+                } else if self.balance_emphasis
+                    && (character == '*' || character == '_')
+                    && code == 0
+                {
+                    if last != character {
+                        emphasis.start_run(last, rest);
+                    }
+
+                    emphasis.delimiter(character);
+                }
+            }
+
+            if character == NEWLINE {
+                if column == 0 {
+                    f.write_str(self.empty_line_fill)?;
+                } else if self.html_line_breaks
+                    && fence.is_none()
+                    && line_fence.is_none()
+                    && continues_paragraph(rest)
+                {
+                    self.close_line(f, &mut content)?;
+                    f.write_str("<br>")?;
+                }
+
+                self.write_line_break(f, &mut content)?;
+            } else {
+                // Strikethrough can't end after whitespace.
+                if rest.trim_end().is_empty() {
+                    self.close_strikethrough(f, &mut content)?;
+                }
+
+                self.write_content(f, &mut content, piece)?;
+            }
+
+            index += size;
+            line_index += size;
+            column += width;
+            url = url.saturating_sub(character.len_utf8());
+            span = span.saturating_sub(character.len_utf8());
+            code = code.saturating_sub(character.len_utf8());
+            last = character;
+            word_start = character.is_whitespace();
+            leading = leading
+                .filter(|_| character == ' ')
+                .map(|spaces| spaces + 1);
+            indenting = indenting && matches!(character, ' ' | TAB);
+
+            if character == NEWLINE {
+                column = 0;
+                line_index = 0;
+                line_number = line_number.saturating_add(1);
+                leading = Some(0);
+                indenting = true;
+                line_truncated = false;
+                fence = Fence::next(fence, line_fence);
+                let previous = Some(depth).filter(|_| paragraph);
+                (skip, depth) = self.skipped_prefix(rest, indentation);
+                let len;
+                (line_fence, len) = self.line_fence(&rest[skip..]);
+                marker = Some(skip + len).filter(|_| len > 0);
+                paragraph = self.lazy && fence.is_none() && continues_paragraph(&rest[skip..]);
+                bare = paragraph && previous == Some(depth);
+                stage = Stage::StartLine;
+                row = fence
+                    .is_none()
+                    .then(|| self.table_row(&rest[skip..], chars.next_line(), indentation, table))
+                    .flatten();
+                table = row.is_some();
+                heading = fence
+                    .is_none()
+                    .then(|| {
+                        let line = &rest[skip..];
+
+                        self.heading(line, chars.next_line(), indentation, text_line)
+                    })
+                    .flatten();
+                text_line =
+                    fence.is_none() && heading.is_none() && continues_paragraph(&rest[skip..]);
+                skip += heading.map_or(0, |heading| heading.skip);
+                dropped = heading.is_some_and(|heading| heading.setext)
+                    || self.image_line(chars.next_line(), Fence::next(fence, line_fence))
+                    || self.is_filtered_out(chars.next_line());
+
+                // Emphasis can't continue past the end of a paragraph.
+                if fence.is_some() || rest[skip..].trim().is_empty() {
+                    emphasis = Emphasis::default();
+                }
+            }
+        }
+
+        // The final line is blank if the text ends with a newline.
+        if stage == Stage::StartLine && position == len {
+            self.write_prefix(f, &mut first, depth)?;
+            self.write_gutter(f, gutter, Some(line_number))?;
+            f.write_str(self.empty_line_fill)?;
+        }
+
+        let truncated = position < end;
+
+        // The fence of the last line is only open if its marker was written.
+        if marker.is_none() && position >= marker_end {
+            fence = Fence::next(fence, line_fence);
+        }
+
+        let fence = fence.filter(|_| {
+            self.fenced.is_some()
+                || truncated && (self.close_code_fences || self.inline_markdown_aware)
+        });
+
+        if let Some(fence) = fence {
+            if column > 0 || stage == Stage::StartLine && position == len {
+                self.write_line_break(f, &mut content)?;
+                self.write_prefix(f, &mut first, depth)?;
+            } else if stage == Stage::StartLine {
+                self.write_prefix(f, &mut first, depth)?;
+            }
+
+            for _ in 0..fence.len {
+                write_char(fence.character, f)?;
+            }
+        }
+
+        if truncated {
+            emphasis.write_closing(f)?;
+        }
+
+        // Tables continue until a blank line, so the ellipsis follows one.
+        if self.with_ellipsis && truncated && table && !line_truncated {
+            if column > 0 {
+                self.write_line_break(f, &mut content)?;
+                self.write_prefix(f, &mut first, depth)?;
+            }
+
+            f.write_str(self.empty_line_fill)?;
+            self.write_line_break(f, &mut content)?;
+            self.write_prefix(f, &mut first, depth)?;
+        }
+
+        // The last line may already end with the ellipsis.
+        if self.with_ellipsis && truncated && !line_truncated {
+            if fence.is_some() {
+                f.write_str(self.line_ending.as_str())?;
+                self.write_prefix(f, &mut first, depth)?;
+            } else if self.ellipsis_on_new_line && column > 0 {
+                self.write_line_break(f, &mut content)?;
+                self.write_prefix(f, &mut first, depth)?;
+            }
+
+            match self.ellipsis_line_marker {
+                Some(marker) if self.ellipsis_on_new_line => f.write_str(marker)?,
+                _ => f.write_str(self.truncation_suffix)?,
+            }
+        }
+
+        self.close_line(f, &mut content)?;
+
+        if self.collapsible.is_some() {
+            f.write_str(self.line_ending.as_str())?;
+            self.write_prefix(f, &mut first, 0)?;
+            f.write_str("</details>")?;
+        }
+
+        if let Some(author) = self.attribution {
+            f.write_str(self.line_ending.as_str())?;
+            self.write_prefix(f, &mut first, 0)?;
+            f.write_str(self.empty_line_fill)?;
+            f.write_str(self.line_ending.as_str())?;
+            self.write_prefix(f, &mut first, 0)?;
+            f.write_str("— ")?;
+
+            if let Some(url) = self.attribution_link {
+                f.write_str("[")?;
+                f.write_str(author)?;
+                f.write_str("](")?;
+                f.write_str(url)?;
+                f.write_str(")")?;
+            } else {
+                f.write_str(author)?;
+            }
+        }
+
+        if self.trailing_newline {
+            f.write_str(self.line_ending.as_str())?;
+        }
+
+        Ok(Formatted {
+            emphasis: truncated && emphasis.len > 0,
+            position,
+            truncated,
+            units: index,
+        })
+    }
+}
+
+impl Display for Blockquote<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        self.format(f).map(drop)
+    }
+}
+
+/// Blockquote formatted once ahead of time, for rendering it repeatedly.
+///
+/// Created by [`Blockquote::prepare`]. It renders the same as the blockquote
+/// it was prepared from, and knows whether the text is cut off and how long
+/// the formatted blockquote is without formatting it again.
+///
+/// # Examples
+///
+/// ```
+/// use markdown_blockquote_formatter::{Blockquote, Boundary};
+///
+/// let prepared = Blockquote::new("- one\n- two")
+///     .break_on(Boundary::ListItem)
+///     .soft_limit(8)
+///     .prepare();
+///
+/// assert_eq!(prepared.to_string(), "> - one…");
+/// assert_eq!(prepared.to_string(), "> - one…");
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PreparedBlockquote<'a> {
+    /// Blockquote formatting in a single pass, if anything fits.
+    blockquote: Option<Blockquote<'a>>,
+    /// Number of characters of the text formatted before it's cut off.
+    emitted_char_count: usize,
+    /// Number of bytes of the formatted blockquote.
+    len: usize,
+    /// Plain text quoted in place of the text, if markdown is stripped.
+    #[cfg(feature = "strip-markdown")]
+    stripped: Option<alloc::string::String>,
+    /// Whether the text is cut off.
+    truncated: bool,
+}
+
+impl PreparedBlockquote<'_> {
+    /// Number of characters of the text formatted before it's cut off.
+    ///
+    /// See [`Blockquote::emitted_char_count`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let prepared = Blockquote::new("hey, this is cool!").soft_limit(9).prepare();
+    ///
+    /// assert_eq!(prepared.emitted_char_count(), 9);
+    /// ```
+    pub const fn emitted_char_count(&self) -> usize {
+        self.emitted_char_count
+    }
+
+    /// Number of bytes of the formatted blockquote.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// let prepared = Blockquote::new("hello").prepare();
+    ///
+    /// assert_eq!(prepared.rendered_len(), 7);
+    /// ```
+    pub const fn rendered_len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the text is cut off by the soft or hard limit.
+    ///
+    /// This is also the case if nothing fits in the [`max_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use markdown_blockquote_formatter::Blockquote;
+    ///
+    /// assert!(Blockquote::new("hey, this is cool!").soft_limit(9).prepare().truncated());
+    /// assert!(!Blockquote::new("hey").soft_limit(9).prepare().truncated());
+    /// ```
+    ///
+    /// [`max_bytes`]: Blockquote::max_bytes
+    pub const fn truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl Display for PreparedBlockquote<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        #[cfg(feature = "strip-markdown")]
+        if let (Some(text), Some(blockquote)) = (&self.stripped, self.blockquote) {
+            return Blockquote {
+                source: Source::Text(text),
+                ..blockquote
+            }
+            .format(f)
+            .map(drop);
+        }
+
+        match self.blockquote {
+            Some(blockquote) => blockquote.format(f).map(drop),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Paragraphs of text formatted as separate blockquotes.
+///
+/// Created by [`Blockquote::quote_each`].
+///
+/// # Examples
+///
+/// ```
+/// use markdown_blockquote_formatter::Blockquote;
+///
+/// let paragraphs = Blockquote::new("one\n\n\ntwo").quote_each();
+///
+/// assert_eq!(paragraphs.to_string(), "> one\n\n> two");
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct QuoteEach<'a> {
+    blockquote: Blockquote<'a>,
+}
+
+impl Display for QuoteEach<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
+        let blockquote = &self.blockquote;
+        let line_ending = blockquote.line_ending.as_str();
+        // Blockquotes ending with a newline are followed by one blank line.
+        let separator = if blockquote.trailing_newline { 1 } else { 2 };
+        let mut len = 0;
+        let mut units = 0;
+        let mut separated = false;
+        let mut start = None;
+        let mut end = 0;
+        let lines = blockquote.content().lines(blockquote.unicode_newlines);
+
+        // Paragraphs end at blank lines and at the end of the text.
+        for line in lines.map(Some).chain(iter::once(None)) {
+            if let Some(line) = line {
+                if !line
+                    .text
+                    .chars()
+                    .all(|character| blockquote.is_blank(character))
+                {
+                    start = start.or(Some(line.position));
+                    end = line.end();
+
+                    continue;
+                }
+            }
+
+            if let Some(start) = start.take() {
+                // Number of line breaks before the blockquote.
+                let before = if separated { separator } else { 0 };
+                let paragraph = Blockquote {
+                    hard_limit_only: blockquote
+                        .hard_limit_only
+                        .map(|hard_limit_only| hard_limit_only.saturating_sub(units)),
+                    max_bytes: blockquote.max_bytes.map(|max_bytes| {
+                        max_bytes.saturating_sub(len + before * line_ending.len())
+                    }),
+                    soft_limit: blockquote.soft_limit.saturating_sub(units),
+                    source: blockquote.content().slice(start, end),
+                    strip_front_matter: false,
+                    ..*blockquote
+                };
+                let mut counter = Counter::default();
+                let formatted = paragraph.format(&mut counter)?;
+
+                if counter.len > 0 {
+                    for _ in 0..before {
+                        f.write_str(line_ending)?;
+                    }
+
+                    paragraph.format(f)?;
+                    len += before * line_ending.len() + counter.len;
+                    separated = true;
+                }
+
+                if formatted.truncated {
+                    return Ok(());
+                }
+
+                units += formatted.units;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Prefixes alternated between lines, borrowed or owned by a
+/// [`BlockquoteBuf`].
+///
+/// Equality and hashing compare the prefixes themselves, regardless of how
+/// they're stored.
+#[derive(Clone, Copy, Debug)]
+enum Prefixes<'a> {
+    /// Prefixes borrowed from the caller.
+    Borrowed(&'a [&'a str]),
+    /// Prefixes owned by an owned blockquote.
+    #[cfg(feature = "alloc")]
+    Owned(&'a [alloc::string::String]),
+}
+
+impl<'a> Prefixes<'a> {
+    /// Prefix at an index, if any.
+    fn get(self, index: usize) -> Option<&'a str> {
+        match self {
+            Self::Borrowed(prefixes) => prefixes.get(index).copied(),
+            #[cfg(feature = "alloc")]
+            Self::Owned(prefixes) => prefixes.get(index).map(|prefix| prefix.as_str()),
+        }
+    }
+
+    /// Iterator over the prefixes.
+    fn iter(self) -> impl Iterator<Item = &'a str> {
+        (0..self.len()).filter_map(move |index| self.get(index))
+    }
+
+    /// Number of prefixes.
+    fn len(self) -> usize {
+        match self {
+            Self::Borrowed(prefixes) => prefixes.len(),
+            #[cfg(feature = "alloc")]
+            Self::Owned(prefixes) => prefixes.len(),
+        }
+    }
+}
+
+impl Eq for Prefixes<'_> {}
+
+impl Hash for Prefixes<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+
+        for prefix in self.iter() {
+            prefix.hash(state);
+        }
+    }
+}
+
+impl PartialEq for Prefixes<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+/// State of the content of the line being formatted.
+#[derive(Clone, Copy, Debug, Default)]
+struct Content {
+    /// Directional formatting opened by the content and not yet closed.
+    directional: Directional,
+    /// Whether content has been written.
+    open: bool,
+    /// Whether strikethrough has been opened.
+    struck: bool,
+}
+
+/// Directional embeddings, overrides, and isolates opened and not yet closed.
+#[derive(Clone, Copy, Debug, Default)]
+struct Directional {
+    /// Number of open embeddings, overrides, and isolates.
+    depth: u32,
+    /// Whether each open level is an isolate, innermost in the lowest bit.
+    isolates: u128,
+}
+
+impl Directional {
+    /// Track a character opening or closing directional formatting.
+    ///
+    /// Closing characters without anything to close are ignored, and so are
+    /// opening characters past the maximum depth, like in the Unicode
+    /// bidirectional algorithm.
+    fn update(&mut self, character: char) {
+        match character {
+            LEFT_TO_RIGHT_EMBEDDING..=RIGHT_TO_LEFT_OVERRIDE
+                if character != POP_DIRECTIONAL_FORMATTING =>
+            {
+                self.push(false);
+            }
+            LEFT_TO_RIGHT_ISOLATE..=FIRST_STRONG_ISOLATE => self.push(true),
+            // Formatting within an isolate can't be closed outside of it.
+            POP_DIRECTIONAL_FORMATTING if self.depth > 0 && self.isolates & 1 == 0 => {
+                self.pop();
+            }
+            // Closing an isolate closes the formatting within it.
+            POP_DIRECTIONAL_ISOLATE if self.isolates != 0 => {
+                while self.pop() == Some(POP_DIRECTIONAL_FORMATTING) {}
+            }
+            _ => {}
+        }
+    }
+
+    /// Open a level of directional formatting.
+    fn push(&mut self, isolate: bool) {
+        if self.depth < u128::BITS {
+            self.depth += 1;
+            self.isolates = self.isolates << 1 | u128::from(isolate);
+        }
+    }
+
+    /// Close the innermost level of directional formatting, returning the
+    /// character closing it.
+    fn pop(&mut self) -> Option<char> {
+        if self.depth == 0 {
+            return None;
+        }
+
+        let isolate = self.isolates & 1 == 1;
+        self.depth -= 1;
+        self.isolates >>= 1;
+
+        Some(if isolate {
+            POP_DIRECTIONAL_ISOLATE
+        } else {
+            POP_DIRECTIONAL_FORMATTING
+        })
+    }
+}
+
+/// Emphasis opened by runs of delimiters and not yet closed.
+#[derive(Clone, Copy, Debug, Default)]
+struct Emphasis {
+    /// Character and number of the open delimiters of each run, innermost
+    /// last.
+    open: [(char, usize); MAX_EMPHASIS_DEPTH],
+    /// Number of runs with open delimiters.
+    len: usize,
+    /// Whether the current run can open and close emphasis.
+    run: (bool, bool),
+    /// Whether the current run has opened emphasis.
+    opening: bool,
+}
+
+impl Emphasis {
+    /// Start a run of delimiters, given the character preceding it and the
+    /// rest of its line starting with it.
+    fn start_run(&mut self, previous: char, rest: &str) {
+        let character = rest.chars().next().unwrap_or_default();
+        let next = rest.trim_start_matches(character).chars().next();
+        let next_whitespace = next.map_or(true, char::is_whitespace);
+        let next_punctuation = next.is_some_and(|next| next.is_ascii_punctuation());
+        let previous_punctuation = previous.is_ascii_punctuation();
+
+        let left_flanking = !next_whitespace
+            && (!next_punctuation || previous.is_whitespace() || previous_punctuation);
+        let right_flanking = !previous.is_whitespace()
+            && (!previous_punctuation || next_whitespace || next_punctuation);
+
+        self.opening = false;
+        self.run = if previous == '\\' {
+            (false, false)
+        } else if character == '_' {
+            (
+                left_flanking && (!right_flanking || previous_punctuation),
+                right_flanking && (!left_flanking || next_punctuation),
+            )
+        } else {
+            (left_flanking, right_flanking)
+        };
+    }
+
+    /// Count a delimiter of the current run.
+    fn delimiter(&mut self, character: char) {
+        let (can_open, can_close) = self.run;
+
+        if can_close && !self.opening {
+            let opener = self.open[..self.len]
+                .iter()
+                .rposition(|(open, _)| *open == character);
+
+            // Emphasis opened within closed emphasis can't be closed anymore.
+            if let Some(index) = opener {
+                self.open[index].1 -= 1;
+                self.len = index + usize::from(self.open[index].1 > 0);
+
+                return;
+            }
+        }
+
+        if !can_open {
+            return;
+        }
+
+        if self.opening {
+            self.open[self.len - 1].1 += 1;
+        } else if self.len < MAX_EMPHASIS_DEPTH {
+            self.open[self.len] = (character, 1);
+            self.len += 1;
+            self.opening = true;
+        }
+    }
+
+    /// Write the delimiters closing the open emphasis.
+    fn write_closing<W: Write>(&self, f: &mut W) -> Result<(), FmtError> {
+        for (character, count) in self.open[..self.len].iter().rev() {
+            for _ in 0..*count {
+                write_char(*character, f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Heading stripped or demoted.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Heading {
+    /// Level of the heading, from one to six.
+    level: usize,
+    /// Whether the heading is a setext heading, underlined on the next line.
+    setext: bool,
+    /// Number of bytes of the heading's markers at the start of its line.
+    skip: usize,
+}
+
+/// Table row that must be kept whole.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct TableRow {
+    /// Whether the row is a header row, along with its delimiter row.
+    header: bool,
+    /// Number of bytes of the row.
+    len: usize,
+    /// Size of the row in the limit unit.
+    size: usize,
+}
+
+/// Text formatted in place of a character of the source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Piece {
+    /// The character itself.
+    Char(char),
+    /// The code point of the character, escaped such as `\u{202E}`.
+    CodePoint(char),
+    /// The value of the ASCII control character, escaped such as `\x07`.
+    Control(char),
+    /// The character escaped with a backslash.
+    Escaped(char),
+    /// A number of spaces.
+    Spaces(usize),
+}
+
+impl Piece {
+    /// Whether the piece is whitespace.
+    fn is_whitespace(self) -> bool {
+        match self {
+            Self::Char(character) => character.is_whitespace(),
+            Self::CodePoint(_) | Self::Control(_) | Self::Escaped(_) => false,
+            Self::Spaces(_) => true,
+        }
+    }
+
+    /// Length of the piece in bytes.
+    const fn len(self) -> usize {
+        match self {
+            Self::Char(character) => character.len_utf8(),
+            Self::CodePoint(character) => code_point_len(character),
+            Self::Control(_) => CONTROL_LEN,
+            Self::Escaped(character) => 1 + character.len_utf8(),
+            Self::Spaces(spaces) => spaces,
+        }
+    }
+
+    /// Number of characters in the piece.
+    const fn width(self) -> usize {
+        match self {
+            Self::Char(_) => 1,
+            Self::CodePoint(character) => code_point_len(character),
+            Self::Control(_) => CONTROL_LEN,
+            Self::Escaped(_) => 2,
+            Self::Spaces(spaces) => spaces,
+        }
+    }
+
+    fn write<W: Write>(self, f: &mut W) -> Result<(), FmtError> {
+        match self {
+            Self::Char(character) => write_char(character, f),
+            Self::CodePoint(character) => write!(f, "\\u{{{:04X}}}", u32::from(character)),
+            Self::Control(character) => write!(f, "\\x{:02X}", u32::from(character)),
+            Self::Escaped(character) => {
+                f.write_str("\\")?;
+
+                write_char(character, f)
+            }
+            Self::Spaces(spaces) => {
+                for _ in 0..spaces {
+                    f.write_str(" ")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Result of formatting a blockquote.
+struct Formatted {
+    /// Whether emphasis was left open where the text was cut off.
+    emphasis: bool,
+    /// Position the text was cut off at, if it was.
+    position: usize,
+    /// Whether the text was cut off by the soft or hard limit.
+    truncated: bool,
+    /// Number of units of the limits the formatted text adds up to.
+    units: usize,
+}
+
+/// Writer counting the bytes written to it.
+#[derive(Debug, Default)]
+struct Counter {
+    len: usize,
+}
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        self.len += s.len();
+
+        Ok(())
+    }
+}
+
+/// Writer counting the line breaks written to it.
+#[derive(Debug, Default)]
+struct LineCounter {
+    breaks: usize,
+    /// Last character written, if any.
+    last: Option<char>,
+}
+
+impl Write for LineCounter {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        self.breaks += s.matches(NEWLINE).count();
+        self.last = s.chars().next_back().or(self.last);
+
+        Ok(())
+    }
+}
+
+/// Writer counting the bytes written through it to another writer.
+struct CountingWriter<'w, W> {
+    inner: &'w mut W,
+    len: usize,
+}
+
+impl<W: Write> Write for CountingWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        self.inner.write_str(s)?;
+        self.len += s.len();
+
+        Ok(())
+    }
+}
+
+/// Writer discarding everything written to it.
+struct Discard;
+
+impl Write for Discard {
+    fn write_str(&mut self, _: &str) -> Result<(), FmtError> {
+        Ok(())
+    }
+}
+
+/// Writer failing once anything is written to it, to find whether anything
+/// is.
+struct Unwritten;
+
+impl Write for Unwritten {
+    fn write_str(&mut self, text: &str) -> Result<(), FmtError> {
+        match text.is_empty() {
+            true => Ok(()),
+            false => Err(FmtError),
+        }
+    }
+}
+
+/// Writer converting lines written through it into an HTML blockquote.
+///
+/// A single line break becomes a `<br>`, and blank lines separate paragraphs.
+/// Leading and trailing line breaks are dropped.
+struct HtmlWriter<'w, W> {
+    /// Number of line breaks not yet written.
+    breaks: usize,
+    inner: &'w mut W,
+    /// Whether the blockquote element has been opened.
+    open: bool,
+}
+
+impl<W: Write> HtmlWriter<'_, W> {
+    /// Close the blockquote element, if anything was written.
+    fn finish(self) -> Result<(), FmtError> {
+        if self.open {
+            self.inner.write_str("</p></blockquote>")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for HtmlWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        // Line endings are written in a single call.
+        if s == LineEnding::CrLf.as_str() {
+            self.breaks += 1;
+
+            return Ok(());
+        }
+
+        s.chars()
+            .try_for_each(|character| self.write_char(character))
+    }
+
+    fn write_char(&mut self, c: char) -> Result<(), FmtError> {
+        if c == NEWLINE {
+            self.breaks += 1;
+
+            return Ok(());
+        }
+
+        if !mem::replace(&mut self.open, true) {
+            self.breaks = 0;
+            self.inner.write_str("<blockquote><p>")?;
+        }
+
+        match mem::take(&mut self.breaks) {
+            0 => {}
+            1 => self.inner.write_str("<br>")?,
+            _ => self.inner.write_str("</p><p>")?,
+        }
+
+        match c {
+            '&' => self.inner.write_str("&amp;"),
+            '<' => self.inner.write_str("&lt;"),
+            '>' => self.inner.write_str("&gt;"),
+            '\u{2026}' => self.inner.write_str("&hellip;"),
+            _ => self.inner.write_char(c),
+        }
+    }
+}
+
+/// Writer finding whether a blank line is written to it.
+#[derive(Debug, Default)]
+struct BlankLineFinder {
+    /// Whether a blank line was written.
+    found: bool,
+    /// Whether a line break was written with nothing after it.
+    line_start: bool,
+}
+
+impl Write for BlankLineFinder {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        for character in s.chars() {
+            match character {
+                NEWLINE => {
+                    self.found |= self.line_start;
+                    self.line_start = true;
+                }
+                CARRIAGE_RETURN => {}
+                _ => self.line_start = false,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writer escaping the braces written through it with backslashes.
+struct BraceEscaper<'w, W> {
+    inner: &'w mut W,
+}
+
+impl<W: Write> Write for BraceEscaper<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        let mut rest = s;
+
+        while let Some(index) = rest.find(['{', '}']) {
+            self.inner.write_str(&rest[..index])?;
+            self.inner.write_str("\\")?;
+            self.inner.write_str(&rest[index..=index])?;
+            rest = &rest[index + 1..];
+        }
+
+        self.inner.write_str(rest)
+    }
+}
+
+/// Writer escaping lines starting with the delimiter closing a block, so the
+/// text can't end the block early.
+///
+/// The delimiter is matched case-insensitively after any indentation, and
+/// held back until the line is known to start with it or not.
+struct DelimiterEscaper<'w, W> {
+    delimiter: &'w str,
+    /// Text written before the delimiter to escape it.
+    escape: &'w str,
+    /// Bytes of the line matching the delimiter so far.
+    held: [u8; 16],
+    inner: &'w mut W,
+    /// Number of bytes of the delimiter matched at the start of the current
+    /// line, or `None` if the line doesn't start with it.
+    matched: Option<usize>,
+}
+
+impl<'w, W: Write> DelimiterEscaper<'w, W> {
+    fn new(inner: &'w mut W, delimiter: &'w str, escape: &'w str) -> Self {
+        debug_assert!(delimiter.len() <= 16);
+
+        Self {
+            delimiter,
+            escape,
+            held: [0; 16],
+            inner,
+            matched: Some(0),
+        }
+    }
+
+    /// Write the bytes held back by a partial match of the delimiter.
+    fn finish(&mut self) -> Result<(), FmtError> {
+        if let Some(matched) = self.matched.take() {
+            // Only ASCII characters of the delimiter are held.
+            let held = str::from_utf8(&self.held[..matched]).map_err(|_| FmtError)?;
+            self.inner.write_str(held)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for DelimiterEscaper<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        if self.matched.is_none() && !s.contains(NEWLINE) {
+            return self.inner.write_str(s);
+        }
+
+        s.chars()
+            .try_for_each(|character| self.write_char(character))
+    }
+
+    fn write_char(&mut self, c: char) -> Result<(), FmtError> {
+        match self.matched {
+            Some(0) if c == ' ' || c == TAB => return self.inner.write_char(c),
+            Some(matched)
+                if c.is_ascii()
+                    && self.delimiter.as_bytes()[matched].eq_ignore_ascii_case(&(c as u8)) =>
+            {
+                self.held[matched] = c as u8;
+
+                if matched + 1 < self.delimiter.len() {
+                    self.matched = Some(matched + 1);
+
+                    return Ok(());
+                }
+
+                self.inner.write_str(self.escape)?;
+                self.matched = Some(self.delimiter.len());
+
+                return self.finish();
+            }
+            _ => self.finish()?,
+        }
+
+        self.inner.write_char(c)?;
+
+        if c == NEWLINE {
+            self.matched = Some(0);
+        }
+
+        Ok(())
+    }
+}
+
+/// Writer indenting every line written through it that isn't blank.
+struct IndentWriter<'w, W> {
+    /// Number of times the indentation is repeated.
+    count: usize,
+    indent: &'w str,
+    inner: &'w mut W,
+    /// Whether nothing has been written on the current line.
+    line_start: bool,
+}
+
+impl<W: Write> Write for IndentWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        if !self.line_start && !s.contains(NEWLINE) {
+            return self.inner.write_str(s);
+        }
+
+        s.chars()
+            .try_for_each(|character| self.write_char(character))
+    }
+
+    fn write_char(&mut self, c: char) -> Result<(), FmtError> {
+        if self.line_start && c != NEWLINE && c != CARRIAGE_RETURN {
+            self.line_start = false;
+
+            for _ in 0..self.count {
+                self.inner.write_str(self.indent)?;
+            }
+        }
+
+        if c == NEWLINE {
+            self.line_start = true;
+        }
+
+        self.inner.write_char(c)
+    }
+}
+
+/// Writer into a byte buffer, never writing a partial character.
+struct ByteWriter<'b> {
+    buf: &'b mut [u8],
+    written: usize,
+}
+
+impl Write for ByteWriter<'_> {
+    fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+        let available = self.buf.len() - self.written;
+        let mut len = s.len().min(available);
+
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        self.buf[self.written..self.written + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.written += len;
+
+        if len == s.len() {
+            Ok(())
+        } else {
+            Err(FmtError)
+        }
+    }
+}
+
+/// Marker of a fenced code block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Fence {
+    /// Character the marker consists of, a backtick or tilde.
+    character: char,
+    /// Number of characters in the marker.
+    len: usize,
+}
+
+impl Fence {
+    /// Open fence after a line, given the fence open before it and the fence
+    /// at the start of the line, if any.
+    fn next(open: Option<Self>, line: Option<(Self, bool)>) -> Option<Self> {
+        match (open, line) {
+            (Some(open), Some((fence, true)))
+                if fence.character == open.character && fence.len >= open.len =>
+            {
+                None
+            }
+            (None, Some((fence, _))) => Some(fence),
+            (open, _) => open,
+        }
+    }
+}
+
+/// Code fence at the start of a line, the number of bytes up to the end of
+/// its marker, and whether it can close a code block.
+fn code_fence(line: &str) -> Option<(Fence, usize, bool)> {
+    let marker = line.trim_start_matches(' ');
+    let indentation = line.len() - marker.len();
+    let character = marker.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = marker.len() - marker.trim_start_matches(character).len();
+
+    if indentation > 3 || len < 3 {
+        return None;
+    }
+
+    let info = &marker[len..];
+
+    // Info strings of backtick fences can't contain backticks.
+    if character == '`' && info.contains('`') {
+        return None;
+    }
+
+    let fence = Fence { character, len };
+
+    Some((fence, indentation + len, info.trim().is_empty()))
+}
+
+/// Length in bytes of the URL starting a word, if any.
+///
+/// URLs start with a scheme followed by `://`, or with `www.`, and end at the
+/// next whitespace.
+fn url_len(word: &str) -> Option<usize> {
+    let len = word.find(char::is_whitespace).unwrap_or(word.len());
+    let token = &word[..len];
+
+    let scheme = token.find("://").is_some_and(|index| {
+        let scheme = &token[..index];
+
+        scheme.starts_with(|character: char| character.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "+-.".contains(character))
+    });
+
+    if scheme || token.starts_with("www.") {
+        Some(len)
+    } else {
+        None
+    }
+}
+
+/// Length in bytes of the inline markdown starting text that must be kept
+/// whole, if any.
+fn inline_markdown_len(text: &str) -> Option<usize> {
+    code_span_len(text)
+        .or_else(|| link_len(text))
+        .or_else(|| autolink_len(text))
+        .or_else(|| html_tag_len(text))
+}
+
+/// Length in bytes of the code span starting text, if any.
+///
+/// Code spans start with a run of backticks and end at the next run of the
+/// same number of backticks on the line.
+fn code_span_len(text: &str) -> Option<usize> {
+    let content = text.trim_start_matches('`');
+    let len = text.len() - content.len();
+
+    if len == 0 {
+        return None;
+    }
+
+    let mut offset = 0;
+
+    while let Some(start) = content[offset..].find('`') {
+        let run = &content[offset + start..];
+        let run_len = run.len() - run.trim_start_matches('`').len();
+
+        offset += start + run_len;
+
+        if run_len == len {
+            return Some(len + offset);
+        }
+    }
+
+    None
+}
+
+/// Length in bytes of the inline link, image, or reference link starting
+/// text, if any.
+fn link_len(text: &str) -> Option<usize> {
+    let image = usize::from(text.starts_with("!["));
+    let label = bracketed_len(&text[image..], '[', ']')?;
+    let destination = &text[image + label..];
+
+    let len = if destination.starts_with('(') {
+        bracketed_len(destination, '(', ')')?
+    } else if destination.starts_with('[') {
+        bracketed_len(destination, '[', ']')?
+    } else {
+        return None;
+    };
+
+    Some(image + label + len)
+}
+
+/// Length in bytes of the image starting text and of its alt text, if any.
+fn image_len(text: &str) -> Option<(usize, usize)> {
+    if !text.starts_with('!') {
+        return None;
+    }
+
+    let len = link_len(text)?;
+    let label = bracketed_len(&text['!'.len_utf8()..], '[', ']')?;
+
+    Some((len, label - "[]".len()))
+}
+
+/// Whether text has at least one image, and otherwise only whitespace.
+fn only_images(text: &str) -> bool {
+    let mut rest = text.trim();
+
+    if rest.is_empty() {
+        return false;
+    }
+
+    while let Some((len, _)) = image_len(rest) {
+        rest = rest[len..].trim_start();
+
+        if rest.is_empty() {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Length in bytes of the balanced brackets starting text, if any.
+///
+/// Brackets escaped with a backslash aren't counted.
+fn bracketed_len(text: &str, open: char, close: char) -> Option<usize> {
+    if !text.starts_with(open) {
+        return None;
+    }
+
+    let mut depth = 0_usize;
+    let mut escaped = false;
+
+    for (index, character) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if character == '\\' {
+            escaped = true;
+        } else if character == open {
+            depth += 1;
+        } else if character == close {
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(index + close.len_utf8());
+            }
+        }
+    }
+
+    None
+}
+
+/// Length in bytes of the autolink starting text, if any.
+///
+/// Autolinks are absolute URIs within angle brackets, and can't contain
+/// whitespace.
+fn autolink_len(text: &str) -> Option<usize> {
+    let uri = text.strip_prefix('<')?;
+    let len = uri.find(|character: char| {
+        character == '>' || character == '<' || character.is_whitespace()
+    })?;
+    let (scheme, _) = uri[..len].split_once(':')?;
+
+    let valid = (2..=32).contains(&scheme.len())
+        && scheme.starts_with(|character: char| character.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "+-.".contains(character));
+
+    if valid && uri[len..].starts_with('>') {
+        Some(len + 2)
+    } else {
+        None
+    }
+}
+
+/// Cells of a table row, separated by pipes not escaped with a backslash.
+///
+/// The pipes at the start and end of the row are optional.
+fn table_cells(line: &str) -> impl Iterator<Item = &str> {
+    let row = line.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let row = match row.strip_suffix('|') {
+        Some(row) if !row.ends_with('\\') => row,
+        _ => row,
+    };
+
+    let mut escaped = false;
+    let mut start = 0;
+
+    row.char_indices()
+        .chain(iter::once((row.len(), '|')))
+        .filter_map(move |(index, character)| {
+            if mem::take(&mut escaped) {
+                return None;
+            }
+
+            match character {
+                '\\' => escaped = true,
+                '|' => {
+                    let cell = &row[start..index];
+                    start = index + 1;
+
+                    return Some(cell);
+                }
+                _ => {}
+            }
+
+            None
+        })
+}
+
+/// Whether a cell of a table's delimiter row is valid.
+///
+/// Delimiter cells consist of dashes, optionally preceded or followed by a
+/// colon to align the column.
+fn is_delimiter_cell(cell: &str) -> bool {
+    let cell = cell.trim();
+    let dashes = cell.strip_prefix(':').unwrap_or(cell);
+    let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+
+    !dashes.is_empty() && dashes.chars().all(|character| character == '-')
+}
+
+/// Length in bytes of the HTML tag starting text, if any.
+///
+/// Tags start with `<` or `</` followed by a letter, and end at the next `>`
+/// outside quoted attribute values on the line.
+fn html_tag_len(text: &str) -> Option<usize> {
+    let tag = text.strip_prefix('<')?;
+    let tag = tag.strip_prefix('/').unwrap_or(tag);
+
+    if !tag.starts_with(|character: char| character.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut quote = None;
+
+    for (index, character) in text.char_indices() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (None, '>') => return Some(index + 1),
+            (Some(open), _) if open == character => quote = None,
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Number of decimal digits of a number.
+fn decimal_len(mut number: usize) -> usize {
+    let mut len = 1;
+
+    while number >= 10 {
+        number /= 10;
+        len += 1;
+    }
+
+    len
+}
+
+/// Whether a character is a bidirectional embedding, override, or isolate
+/// control.
+fn is_bidi_control(character: char) -> bool {
+    matches!(
+        character,
+        LEFT_TO_RIGHT_EMBEDDING..=RIGHT_TO_LEFT_OVERRIDE
+            | LEFT_TO_RIGHT_ISOLATE..=POP_DIRECTIONAL_ISOLATE
+    )
+}
+
+/// Length of the ANSI escape sequence starting text, if it starts with a
+/// complete one.
+///
+/// Control sequences consist of parameter and intermediate bytes followed by
+/// a final byte, and operating system commands are terminated by a bell or
+/// string terminator.
+fn ansi_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+
+    match bytes.get(..2)? {
+        [0x1B, b'['] => {
+            let body = &bytes[2..];
+            let parameters = body
+                .iter()
+                .take_while(|byte| (0x20..=0x3F).contains(*byte))
+                .count();
+
+            match body.get(parameters)? {
+                0x40..=0x7E => Some(2 + parameters + 1),
+                _ => None,
+            }
+        }
+        [0x1B, b']'] => bytes[2..]
+            .iter()
+            .enumerate()
+            .find_map(|(index, byte)| match byte {
+                0x07 => Some(2 + index + 1),
+                0x1B if bytes.get(2 + index + 1) == Some(&b'\\') => Some(2 + index + 2),
+                _ => None,
+            }),
+        _ => None,
+    }
+}
+
+/// Whether a character is an ASCII control other than a newline, carriage
+/// return, or tab.
+fn is_control(character: char) -> bool {
+    character.is_ascii_control() && !matches!(character, NEWLINE | CARRIAGE_RETURN | TAB)
+}
+
+/// Length of the escape of a character's code point, with at least four hex
+/// digits.
+const fn code_point_len(character: char) -> usize {
+    let mut digits = 4;
+
+    while digits < 8 && (character as u32) >> (digits * 4) > 0 {
+        digits += 1;
+    }
+
+    "\\u{}".len() + digits
+}
+
+/// Whether a character is an emoji, or a modifier or variation selector
+/// ending one.
+fn is_emoji(character: char) -> bool {
+    matches!(
+        character,
+        '\u{2300}'..='\u{23FF}'
+            | '\u{2600}'..='\u{27BF}'
+            | '\u{2B00}'..='\u{2BFF}'
+            | '\u{FE0F}'
+            | '\u{1F000}'..='\u{1FAFF}'
+    )
+}
+
+/// Whether a zero-width character is removed when [stripping them].
+///
+/// [stripping them]: Blockquote::strip_zero_width
+fn is_strippable(character: char) -> bool {
+    matches!(character, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
+
+/// Whether a character is invisible and takes up no space, such as format
+/// characters and bidirectional controls.
+fn is_zero_width(character: char) -> bool {
+    matches!(
+        character,
+        '\u{AD}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+    )
+}
+
+/// Length of an alert marker such as `[!NOTE]` making up a line, if it is
+/// one.
+fn alert_marker_len(line: &str) -> Option<usize> {
+    let kind = line.strip_prefix("[!")?;
+    let len = kind.find(']')?;
+
+    let valid = len > 0
+        && kind[..len].bytes().all(|byte| byte.is_ascii_alphabetic())
+        && kind[len + "]".len()..].trim_end().is_empty();
+
+    valid.then(|| "[!".len() + len + "]".len())
+}
+
+/// Whether a line without indentation starts a list item.
+fn is_list_item(line: &str) -> bool {
+    let marker = line.trim_start_matches(|character: char| character.is_ascii_digit());
+    let digits = line.len() - marker.len();
+
+    let rest = match digits {
+        0 => line.strip_prefix(['*', '+', '-']),
+        1..=9 => marker.strip_prefix(['.', ')']),
+        _ => None,
+    };
+
+    rest.is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', TAB]))
+}
+
+/// Whether a line can lazily continue a paragraph, rather than being blank or
+/// possibly starting another block.
+fn continues_paragraph(line: &str) -> bool {
+    let content = line.trim_start_matches(' ');
+
+    if line.len() - content.len() > 3 {
+        return false;
+    }
+
+    content.chars().next().is_some_and(|character| {
+        !character.is_ascii_digit()
+            && !character.is_whitespace()
+            && !"#*+-<=>`|~".contains(character)
+    })
+}
+
+/// Number of bytes of the quote markers at the start of a line, and the number
+/// of quote markers.
+///
+/// ANSI escape sequences around the markers are skipped if they're stripped.
+fn quote_markers(line: &str, strip_ansi: bool) -> (usize, usize) {
+    let mut bytes = 0;
+    let mut depth = 0;
+
+    loop {
+        while let Some(len) = ansi_len(&line[bytes..]).filter(|_| strip_ansi) {
+            bytes += len;
+        }
+
+        let rest = &line[bytes..];
+        let marker = rest.trim_start_matches(' ');
+        let indentation = rest.len() - marker.len();
+
+        if indentation > 3 || !marker.starts_with('>') {
+            break;
+        }
+
+        bytes += indentation + 1;
+        depth += 1;
+
+        if line[bytes..].starts_with(' ') {
+            bytes += 1;
+        }
+    }
+
+    (bytes, depth)
+}
+
+/// Length in bytes of the longest common prefix of two strings.
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(a.len().min(b.len()), |((position, _), _)| position)
+}
+
+fn write_char<W: Write>(character: char, f: &mut W) -> Result<(), FmtError> {
+    let mut buf = [0u8; 4];
+    let string_slice = character.encode_utf8(&mut buf);
+
+    f.write_str(string_slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AlertKind, BacktickPolicy, BidiPolicy, Blockquote, BlockquoteError, Boundary,
+        CapacityError, ControlPolicy, EscapeProfile, Format, HeadingPolicy, ImagePolicy,
+        LimitOverflow, LimitUnit, LineEnding, MinCharsPolicy, NestingStyle, PrefixMode,
+        PreparedBlockquote, QuoteEach,
+    };
+    use alloc::{
+        borrow::ToOwned,
+        fmt::Debug,
+        format,
+        string::{String, ToString},
+        vec::Vec,
+    };
+    use core::{
+        fmt::{Error as FmtError, Write},
+        hash::Hash,
+    };
+    use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+    use static_assertions::{assert_impl_all, const_assert};
+
+    assert_impl_all!(AlertKind: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(BidiPolicy: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(Blockquote: Debug, Hash, Send, Sync);
+    assert_impl_all!(BlockquoteError: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(Boundary: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(CapacityError: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(ControlPolicy: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(HeadingPolicy: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(BacktickPolicy: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(EscapeProfile: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(Format: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(ImagePolicy: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(LimitOverflow: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(LimitUnit: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(LineEnding: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(MinCharsPolicy: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(NestingStyle: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(PrefixMode: Clone, Copy, Debug, Hash, Send, Sync);
+    assert_impl_all!(PreparedBlockquote: Clone, Debug, Hash, Send, Sync);
+    assert_impl_all!(QuoteEach: Clone, Copy, Debug, Hash, Send, Sync);
+    #[cfg(feature = "alloc")]
+    assert_impl_all!(super::BlockquoteBuf: Clone, Debug, Hash, Send, Sync);
+
+    /// Events of markdown parsed by CommonMark.
+    fn parse(markdown: &str) -> Vec<Event<'_>> {
+        Parser::new(markdown).collect()
+    }
+
+    /// Events of the contents of a blockquote parsed by CommonMark, asserting
+    /// that the markdown is a single blockquote.
+    fn unquote(quoted: &str) -> Vec<Event<'_>> {
+        let mut events = parse(quoted);
+
+        assert_eq!(events.first(), Some(&Event::Start(Tag::BlockQuote(None))));
+        assert_eq!(events.last(), Some(&Event::End(TagEnd::BlockQuote(None))));
+
+        events.remove(0);
+        events.pop();
+
+        events
+    }
+
+    #[test]
+    fn test_round_trip() {
+        const INPUTS: &[&str] = &[
+            "plain text",
+            "> quoted\nnot quoted >",
+            ">> nested > mid\n  >indented",
+            "*a* **b** * c _d_ __e__",
+            "`code` ``` fence\n```\ncode\n```",
+            "a\n\n> b\n\n  * list\n    continued",
+            "\ta\tb",
+            "a  \nb",
+            " leading\n\n\n  trailing   \n\n",
+            "    indented code\n>",
+            "\\> escaped",
+            "a > b\n> c\n>\n>> d",
+            "*open `tick\n* item ``a`b``\n\n***",
+            "```\n> in code\n```",
+            "> lazy\ncontinuation",
+            "1 > 0 and `a > b`\n*\n`",
+            "- a\n\n\t- b\n\n\t\tcode",
+        ];
+
+        for input in INPUTS {
+            let expected = parse(input.trim_start_matches('\n').trim_end());
+
+            let formatted = Blockquote::new(input).to_string();
+            assert_eq!(unquote(&formatted), expected, "{:?}", formatted);
+
+            let formatted = Blockquote::new(input)
+                .nesting_style(NestingStyle::Contiguous)
+                .to_string();
+            assert_eq!(unquote(&formatted), expected, "{:?}", formatted);
+        }
+    }
+
+    #[test]
+    fn test_simple() {
+        const INPUT: &str = "this is a simple test";
+        const OUTPUT: &str = "> this is a simple test";
+
+        let formatter = Blockquote::new(INPUT).soft_limit(50);
+
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(Blockquote::new("").is_empty());
+        assert!(Blockquote::new(" \n  \t ").is_empty());
+    }
+
+    #[test]
+    fn test_is_definitely_empty() {
+        const_assert!(Blockquote::new("").is_definitely_empty());
+        const_assert!(Blockquote::from_lines(&["", ""]).is_definitely_empty());
+        const_assert!(!Blockquote::new(" ").is_definitely_empty());
+        const_assert!(!Blockquote::from_lines(&["", "a"]).is_definitely_empty());
+    }
+
+    #[test]
+    fn test_first_line() {
+        let formatter = Blockquote::new("single line");
+        assert_eq!(formatter.first_line().to_string(), "> single line");
+
+        let formatter = Blockquote::new(" \n\nfirst  \nsecond\nthird");
+        assert_eq!(formatter.first_line().to_string(), "> first…");
+        assert_eq!(formatter.first_line().soft_limit(3).to_string(), "> fir…");
+        assert_eq!(
+            formatter
+                .trim_leading_blank_lines(false)
+                .first_line()
+                .to_string(),
+            "> first…"
+        );
+        assert_eq!(
+            formatter.with_ellipsis(false).first_line().to_string(),
+            "> first"
+        );
+
+        let formatter = Blockquote::from_lines(&["", "one", "two"]);
+        assert_eq!(formatter.first_line().to_string(), "> one…");
+
+        assert_eq!(Blockquote::new("").first_line().to_string(), "");
+        assert_eq!(Blockquote::new(" \n ").first_line().to_string(), "");
+    }
+
+    #[test]
+    fn test_zero_width_is_empty() {
+        for text in ["\u{200B}", "\u{FEFF}", " \u{200B}\n\u{FEFF}\u{200D} "] {
+            let formatter = Blockquote::new(text);
+            assert!(!formatter.is_empty());
+            assert!(formatter.zero_width_is_empty(true).is_empty());
+            assert_eq!(formatter.zero_width_is_empty(true).to_string(), "");
+            assert_eq!(
+                formatter
+                    .zero_width_is_empty(true)
+                    .header("header")
+                    .to_string(),
+                ""
+            );
+        }
+
+        let formatter = Blockquote::new("\u{200B}a").zero_width_is_empty(true);
+        assert!(!formatter.is_empty());
+        assert_eq!(formatter.to_string(), "> \u{200B}a");
+    }
+
+    #[test]
+    fn test_strip_zero_width() {
+        const INPUT: &str = "hey,\u{200B}\u{200C} this\u{FEFF} is\u{2060} cool!";
+
+        // The zero-width characters count toward the soft limit.
+        let formatter = Blockquote::new(INPUT).soft_limit(9);
+        assert_eq!(formatter.to_string(), "> hey,\u{200B}\u{200C} th…");
+
+        let formatter = formatter.strip_zero_width(true);
+        assert_eq!(formatter.to_string(), "> hey, this…");
+        assert_eq!(
+            formatter.soft_limit(usize::MAX).to_string(),
+            "> hey, this is cool!"
+        );
+
+        let formatter = Blockquote::new("\u{200B}\n\u{FEFF}\u{200D}").strip_zero_width(true);
+        assert!(formatter.is_empty());
+        assert_eq!(formatter.to_string(), "");
+
+        let formatter = Blockquote::new("a\u{200B}\nb\u{200B}\n\u{200B}").strip_zero_width(true);
+        assert_eq!(formatter.to_string(), "> a\n> b");
+    }
+
+    #[test]
+    fn test_strip_zero_width_emoji() {
+        // Joiners between emoji are kept, including after variation selectors
+        // and skin tone modifiers.
+        const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        const HEART: &str = "\u{2764}\u{FE0F}\u{200D}\u{1F525}";
+        const RUNNER: &str = "\u{1F3C3}\u{1F3FD}\u{200D}\u{2640}\u{FE0F}";
+
+        for emoji in [FAMILY, HEART, RUNNER] {
+            let formatter = Blockquote::new(emoji).strip_zero_width(true);
+            assert_eq!(formatter.to_string(), format!("> {}", emoji));
+        }
+
+        // Joiners not between emoji are removed.
+        let formatter = Blockquote::new("a\u{200D}\u{1F468} \u{1F468}\u{200D}b \u{1F468}\u{200D}")
+            .strip_zero_width(true);
+        assert_eq!(formatter.to_string(), "> a\u{1F468} \u{1F468}b \u{1F468}");
+
+        // Kept joiners count toward the limits.
+        let formatter = Blockquote::new(FAMILY).strip_zero_width(true).soft_limit(3);
+        assert_eq!(formatter.to_string(), "> \u{1F468}\u{200D}\u{1F469}…");
+    }
+
+    #[test]
+    fn test_newlines() {
+        const EXPECTED: &str = "> test\n> two\n> three";
+
+        let formatter = Blockquote::new("test\ntwo\nthree").soft_limit(50);
+
+        assert_eq!(formatter.to_string(), EXPECTED);
+    }
+
+    #[test]
+    fn test_soft_limit() {
+        const EXPECTED: &str = "> this is just:\n> a really cool…";
+
+        let before_cut = "this is just:\na really co";
+        let after_cut = "ol test!";
+        let text = before_cut.to_owned() + after_cut;
+
+        let formatter = Blockquote::new(&text)
+            .soft_limit(before_cut.len())
+            .hard_limit(10);
+
+        assert_eq!(formatter.to_string(), EXPECTED);
+    }
+
+    #[test]
+    fn test_soft_limit_ratio() {
+        const INPUT: &str = "one two three four five six seven";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(
+            formatter.soft_limit_ratio(1.0).to_string(),
+            "> one two three four five six seven"
+        );
+        assert_eq!(
+            formatter.soft_limit_ratio(2.0).to_string(),
+            "> one two three four five six seven"
+        );
+        assert_eq!(
+            formatter.soft_limit_ratio(0.5).to_string(),
+            "> one two three fo…"
+        );
+        assert_eq!(formatter.soft_limit_ratio(0.5).emitted_char_count(), 16);
+        assert_eq!(formatter.soft_limit_ratio(0.0).to_string(), "> …");
+        assert_eq!(formatter.soft_limit_ratio(-1.0).to_string(), "> …");
+        assert_eq!(formatter.soft_limit_ratio(f32::NAN).to_string(), "> …");
+
+        let formatter = Blockquote::from_lines(&["ab", "cd"]).soft_limit_ratio(0.5);
+        assert_eq!(formatter.to_string(), "> ab…");
+
+        #[cfg(feature = "alloc")]
+        {
+            let formatter = super::BlockquoteBuf::new("héllo wörld").soft_limit_ratio(0.5);
+            assert_eq!(formatter.to_string(), "> héllo…");
+        }
+    }
+
+    #[test]
+    fn test_line_numbers() {
+        const INPUT: &str = "fn main() {\n\n    let x = 5;\n}";
+
+        let formatter = Blockquote::new(INPUT).line_numbers(98);
+        assert_eq!(
+            formatter.to_string(),
+            ">  98 | fn main() {\n>  99 | \n> 100 |     let x = 5;\n> 101 | }"
+        );
+        assert_eq!(
+            formatter.line_numbers(1).to_string(),
+            "> 1 | fn main() {\n> 2 | \n> 3 |     let x = 5;\n> 4 | }"
+        );
+
+        // The gutter doesn't count toward the soft and hard limits.
+        assert_eq!(
+            formatter.soft_limit(13).to_string(),
+            ">  98 | fn main() {\n>  99 | \n> 100 | …"
+        );
+
+        // The gutter counts toward the wrap width.
+        let formatter = Blockquote::new("one two three").line_numbers(9).wrap(10);
+        assert_eq!(formatter.to_string(), "> 9 | one\n>   | two\n>   | three");
+
+        let formatter = Blockquote::new("a\nb").line_numbers(9).max_bytes(12);
+        assert_eq!(formatter.to_string(), ">  9 | a…");
+
+        // Lines are numbered by their position in the text, and only the
+        // formatted lines widen the gutter.
+        let formatter = Blockquote::new("\n\na\nb\n\n").line_numbers(6);
+        assert_eq!(formatter.to_string(), "> 8 | a\n> 9 | b");
+        assert_eq!(
+            formatter.keep_trailing_blank_lines(true).to_string(),
+            ">  8 | a\n>  9 | b\n> 10 | \n> 11 | "
+        );
+
+        // Numbers saturate rather than overflowing.
+        let formatter = Blockquote::new("a\nb").line_numbers(usize::MAX);
+        assert_eq!(
+            formatter.to_string(),
+            format!("> {0} | a\n> {0} | b", usize::MAX)
+        );
+    }
+
+    #[test]
+    fn test_prepare() {
+        const INPUT: &str = "- this is just\n- a really cool\n- test";
+
+        let formatters = [
+            (Blockquote::new(INPUT), false),
+            (Blockquote::new(INPUT).soft_limit(20), true),
+            (Blockquote::new(INPUT).max_bytes(30), true),
+            (Blockquote::new(INPUT).max_bytes(1), true),
+            (
+                Blockquote::new(INPUT)
+                    .soft_limit(20)
+                    .ellipsis_replaces(true),
+                true,
+            ),
+            (
+                Blockquote::new(INPUT)
+                    .soft_limit(20)
+                    .break_on(Boundary::ListItem),
+                true,
+            ),
+            (
+                Blockquote::new(INPUT)
+                    .max_bytes(40)
+                    .ellipsis_replaces(true)
+                    .break_on(Boundary::ListItem),
+                true,
+            ),
+            (Blockquote::new(" \n "), false),
+        ];
+
+        for (formatter, truncated) in formatters {
+            let expected = formatter.to_string();
+            let prepared = formatter.prepare();
+
+            assert_eq!(prepared.to_string(), expected);
+            assert_eq!(prepared.to_string(), expected);
+            assert_eq!(prepared.rendered_len(), expected.len());
+            assert_eq!(prepared.truncated(), truncated);
+            assert_eq!(
+                prepared.emitted_char_count(),
+                formatter.emitted_char_count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_soft_limit_cutoff() {
+        const EXPECTED: &str = "> this is just:\n> a really coo…";
+
+        let before_cut = "this is just:\na really co";
+        let after_cut = "ol test!";
+        let text = before_cut.to_owned() + after_cut;
+
+        let formatter = Blockquote::new(&text)
+            .soft_limit(before_cut.len())
+            .hard_limit(1);
+
+        assert_eq!(formatter.to_string(), EXPECTED);
+    }
+
+    #[test]
+    fn test_try_hard_limit() {
+        let formatter = Blockquote::new("abc").soft_limit(usize::MAX - 1);
+        assert!(formatter.try_hard_limit(0).is_ok());
+        assert!(formatter.try_hard_limit(1).is_ok());
+        assert_eq!(formatter.try_hard_limit(2), Err(LimitOverflow));
+
+        let formatter = Blockquote::new("abc").soft_limit(1);
+        assert!(formatter.try_hard_limit(usize::MAX - 1).is_ok());
+        assert_eq!(formatter.try_hard_limit(usize::MAX), Err(LimitOverflow));
+        assert_eq!(formatter.try_hard_limit(1), Ok(formatter.hard_limit(1)));
+    }
+
+    #[test]
+    fn test_soft_limit_none() {
+        const INPUT: &str = "this text is too long :(";
+        const OUTPUT: &str = "> this text is too lo…";
+
+        let formatter = Blockquote::new(INPUT).soft_limit(19);
+
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_soft_limit_zero() {
+        const INPUT: &str = "this text is too long :(";
+        const OUTPUT: &str = "> this text is too lo…";
+
+        let formatter = Blockquote::new(INPUT).soft_limit(19);
+
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_end_with_newline() {
+        const INPUT: &str = "test\nagain\n\n";
+        const OUTPUT: &str = "> test\n> again";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_wrap() {
+        const INPUT: &str = "a quote that is long enough to wrap\nshort";
+        const OUTPUT: &str = "> a quote\n> that is\n> long\n> enough to\n> wrap\n> short";
+
+        let formatter = Blockquote::new(INPUT).wrap(10);
+
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_wrap_long_word() {
+        const INPUT: &str = "a supercalifragilistic word";
+        const OUTPUT: &str = "> a\n> supercalif\n> ragilistic\n> word";
+
+        let formatter = Blockquote::new(INPUT).wrap(10);
+
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_wrap_width() {
+        let token = "x".repeat(60);
+        let mut input = String::new();
+
+        while input.len() < 200 {
+            input.push_str("wrap this ");
+        }
+
+        input.push_str(&token);
+
+        while input.len() < 300 {
+            input.push_str(" text");
+        }
+
+        let formatted = Blockquote::new(&input).wrap(40).to_string();
+        let lines = formatted.split('\n').collect::<Vec<_>>();
+
+        assert!(lines.iter().all(|line| line.starts_with("> ")));
+        assert!(lines.iter().all(|line| line[2..].chars().count() <= 40));
+        assert_eq!(input.len(), 300);
+        assert_eq!(lines.len(), 8);
+
+        // The token is broken at the width.
+        assert_eq!(lines[5], format!("> {}", &token[..40]));
+        assert_eq!(lines[6], format!("> {} text text text text", &token[40..]));
+
+        // Only whitespace is replaced by wraps.
+        let content = lines.iter().map(|line| &line[2..]).collect::<Vec<_>>();
+        assert_eq!(content.concat().replace(' ', ""), input.replace(' ', ""));
+    }
+
+    #[test]
+    fn test_wrap_continuation() {
+        const INPUT: &str = "first line wraps here\nsecond\nthird is supercalifragilistic";
+        const OUTPUT: &str =
+            "> first line\n>   wraps here\n> second\n> third is\n>   supercalif\n>   ragilistic";
+
+        let formatter = Blockquote::new(INPUT).wrap(10).wrap_continuation(">   ");
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_wrap_trailing_whitespace() {
+        const INPUT: &str = "abc def \nghi  \n";
+
+        let formatter = Blockquote::new("abc  \nd").wrap(3);
+        assert_eq!(formatter.to_string(), "> abc\n> d");
+
+        let formatter = Blockquote::new(INPUT).wrap(7);
+        assert_eq!(formatter.to_string(), "> abc def\n> ghi");
+
+        let formatter = formatter.keep_trailing_blank_lines(true);
+        assert_eq!(formatter.to_string(), "> abc def\n> ghi  \n> ");
+    }
+
+    #[test]
+    fn test_wrap_url() {
+        const INPUT: &str = "see https://example.com/a/very/long/path and www.example.com/page";
+
+        // URLs are wrapped before and kept whole even if longer than the width.
+        let formatter = Blockquote::new(INPUT).wrap(20);
+        assert_eq!(
+            formatter.to_string(),
+            "> see\n> https://example.com/a/very/long/path\n> and\n> www.example.com/page"
+        );
+
+        // Other words are still broken.
+        let formatter = Blockquote::new("see httpsexamplecom/a/very/long/path").wrap(20);
+        assert_eq!(
+            formatter.to_string(),
+            "> see\n> httpsexamplecom/a/ve\n> ry/long/path"
+        );
+    }
+
+    #[test]
+    fn test_url_limit() {
+        const INPUT: &str = "read https://example.com/docs now";
+
+        // URLs straddling the limit are cut before entirely.
+        let formatter = Blockquote::new(INPUT).soft_limit(10);
+        assert_eq!(formatter.to_string(), "> read…");
+        let formatter = Blockquote::new(INPUT).soft_limit(10).hard_limit(10);
+        assert_eq!(formatter.to_string(), "> read…");
+        let formatter = Blockquote::new(INPUT).hard_limit_only(29);
+        assert_eq!(formatter.to_string(), "> read https://example.com/docs…");
+
+        // URLs starting a line are cut before after the line's prefix.
+        let formatter = Blockquote::new("read\nwww.example.com").soft_limit(8);
+        assert_eq!(formatter.to_string(), "> read\n> …");
+        let formatter = Blockquote::new("ftp://example.com").soft_limit(8);
+        assert_eq!(formatter.to_string(), "> …");
+
+        // Schemes must start with a letter.
+        let formatter = Blockquote::new("1://example.com").soft_limit(8);
+        assert_eq!(formatter.to_string(), "> 1://exam…");
+    }
+
+    #[test]
+    fn test_inline_code_span_limit() {
+        const INPUT: &str = "run `cargo test` now";
+
+        // Code spans straddling the limit are cut before entirely.
+        let formatter = Blockquote::new(INPUT).inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(10).to_string(), "> run…");
+        assert_eq!(formatter.hard_limit_only(8).to_string(), "> run…");
+        assert_eq!(formatter.soft_limit(3).hard_limit(20).to_string(), "> run…");
+
+        // Code spans just fitting are kept.
+        assert_eq!(formatter.soft_limit(16).to_string(), "> run `cargo test`…");
+        assert_eq!(
+            formatter.hard_limit_only(16).to_string(),
+            "> run `cargo test`…"
+        );
+
+        // Code spans longer than the limits leave only the ellipsis.
+        let formatter = Blockquote::new("`cargo test`").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(5).to_string(), "> …");
+
+        // Code spans may start within words and contain shorter backtick runs.
+        let formatter = Blockquote::new("a``b ` c``d").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(6).to_string(), "> a…");
+        assert_eq!(formatter.soft_limit(10).to_string(), "> a``b ` c``…");
+
+        // Unmatched backticks and backticks in code blocks are literal, and
+        // code blocks are closed.
+        let formatter = Blockquote::new("a ``b` c").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(5).to_string(), "> a ``b…");
+        let formatter = Blockquote::new("```\na `b c`\n```").inline_markdown_aware(true);
+        assert_eq!(
+            formatter.soft_limit(8).to_string(),
+            "> ```\n> a `b\n> ```\n> …"
+        );
+    }
+
+    #[test]
+    fn test_inline_link_limit() {
+        const INPUT: &str = "see [the docs](https://docs.rs) now";
+
+        // Links straddling the limit are cut before entirely, whether the
+        // limit falls in their label or their destination.
+        let formatter = Blockquote::new(INPUT).inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(8).to_string(), "> see…");
+        assert_eq!(formatter.soft_limit(20).to_string(), "> see…");
+        assert_eq!(formatter.hard_limit_only(30).to_string(), "> see…");
+
+        // Links just fitting are kept.
+        assert_eq!(
+            formatter.hard_limit_only(31).to_string(),
+            "> see [the docs](https://docs.rs)…"
+        );
+
+        // Reference links, images, and autolinks are kept whole too.
+        let formatter = Blockquote::new("see [the docs][docs] now").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(16).to_string(), "> see…");
+        assert_eq!(
+            formatter.soft_limit(20).to_string(),
+            "> see [the docs][docs]…"
+        );
+        let formatter = Blockquote::new("a ![logo](logo.png)").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(10).to_string(), "> a…");
+        let formatter = Blockquote::new("at <https://docs.rs> now").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(10).to_string(), "> at…");
+        assert_eq!(
+            formatter.soft_limit(20).to_string(),
+            "> at <https://docs.rs>…"
+        );
+
+        // Links alone longer than the limits leave only the ellipsis.
+        let formatter = Blockquote::new("[docs](https://docs.rs)").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(10).to_string(), "> …");
+
+        // Nested and escaped brackets are balanced.
+        let formatter = Blockquote::new("[a [b] \\]](c) d").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(13).to_string(), "> [a [b] \\]](c)…");
+        assert_eq!(formatter.soft_limit(12).to_string(), "> …");
+
+        // Other brackets are literal.
+        let formatter = Blockquote::new("[a] <1> c").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(6).to_string(), "> [a] <1…");
+    }
+
+    #[test]
+    fn test_balance_emphasis() {
+        const INPUT: &str = "it's *so **very** cool* and __neat__";
+
+        let formatter = Blockquote::new(INPUT).balance_emphasis(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> it's *so **very** cool* and __neat__"
+        );
+
+        // Single and double markers are closed, innermost first.
+        assert_eq!(formatter.soft_limit(8).to_string(), "> it's *so*…");
+        assert_eq!(formatter.soft_limit(13).to_string(), "> it's *so **ve***…");
+        assert_eq!(
+            formatter.soft_limit(15).to_string(),
+            "> it's *so **very***…"
+        );
+        assert_eq!(
+            formatter.soft_limit(32).to_string(),
+            "> it's *so **very** cool* and __ne__…"
+        );
+
+        // Markers opened on previous lines of the paragraph are closed.
+        let formatter = Blockquote::new("_first\nsecond_").balance_emphasis(true);
+        assert_eq!(formatter.soft_limit(10).to_string(), "> _first\n> sec_…");
+
+        // But not those of previous paragraphs.
+        let formatter = Blockquote::new("_first\n\nsecond_").balance_emphasis(true);
+        assert_eq!(formatter.soft_limit(11).to_string(), "> _first\n> \n> sec…");
+
+        // Escaped markers, intraword underscores, and markers in code aren't
+        // delimiters.
+        let formatter = Blockquote::new("\\*a snake_case `*b` c*").balance_emphasis(true);
+        assert_eq!(
+            formatter.soft_limit(19).to_string(),
+            "> \\*a snake_case `*b`…"
+        );
+        let formatter = Blockquote::new("```\n*a\n```").balance_emphasis(true);
+        assert_eq!(formatter.soft_limit(6).to_string(), "> ```\n> *a…");
+
+        // Intraword asterisks are delimiters.
+        let formatter = Blockquote::new("un*frigging*believable").balance_emphasis(true);
+        assert_eq!(formatter.soft_limit(5).to_string(), "> un*fr*…");
+    }
+
+    #[test]
+    fn test_balance_emphasis_whitespace() {
+        // Emphasis is closed before whitespace the text is cut off after, as
+        // it can't be closed after whitespace.
+        let formatter = Blockquote::new("it's *so **very** cool*")
+            .balance_emphasis(true)
+            .soft_limit(9);
+        assert_eq!(formatter.to_string(), "> it's *so*…");
+        assert!(unquote(&formatter.to_string()).contains(&Event::Start(Tag::Emphasis)));
+
+        let formatter = Blockquote::new("some **bold text here** x")
+            .balance_emphasis(true)
+            .soft_limit(12);
+        assert_eq!(formatter.to_string(), "> some **bold**…");
+        assert!(unquote(&formatter.to_string()).contains(&Event::Start(Tag::Strong)));
+
+        let formatter = Blockquote::new("a _b c_")
+            .balance_emphasis(true)
+            .soft_limit(5);
+        assert_eq!(formatter.to_string(), "> a _b_…");
+        assert!(unquote(&formatter.to_string()).contains(&Event::Start(Tag::Emphasis)));
+
+        // Including line breaks and the whitespace of the next line.
+        let formatter = Blockquote::new("a _b\n c_")
+            .balance_emphasis(true)
+            .soft_limit(5);
+        assert_eq!(formatter.to_string(), "> a _b_…");
+
+        // Whitespace is kept if no emphasis is open.
+        let formatter = Blockquote::new("it's so very cool").balance_emphasis(true);
+        assert_eq!(formatter.soft_limit(8).to_string(), "> it's so …");
+    }
+
+    #[test]
+    fn test_inline_table_limit() {
+        const INPUT: &str = "Intro\n| a | b |\n|---|:-:|\n| 1 | 2 |\n| 3 | 4 |\nafter";
+        const TABLE: &str = "> Intro\n> | a | b |\n> |---|:-:|\n> | 1 | 2 |";
+
+        let formatter = Blockquote::new(INPUT).inline_markdown_aware(true);
+
+        // Tables are cut before entirely if their header doesn't fit, whether
+        // the limit falls in the header or the delimiter row.
+        assert_eq!(formatter.soft_limit(10).to_string(), "> Intro\n> …");
+        assert_eq!(formatter.soft_limit(20).to_string(), "> Intro\n> …");
+
+        // Otherwise they're cut before the first row that doesn't fit, with
+        // the ellipsis after a blank line.
+        let output = format!("{}\n> \n> …", TABLE);
+        assert_eq!(formatter.soft_limit(40).to_string(), output);
+        assert_eq!(formatter.hard_limit_only(44).to_string(), output);
+
+        // Rows just fitting are kept.
+        let output = format!("{}\n> | 3 | 4 |\n> \n> …", TABLE);
+        assert_eq!(formatter.soft_limit(45).to_string(), output);
+        assert_eq!(formatter.soft_limit(47).to_string(), output);
+
+        // Lines without pipes continue tables until a blank line.
+        let formatter = Blockquote::new("| a |\n| - |\nb\n\nc").inline_markdown_aware(true);
+        assert_eq!(
+            formatter.soft_limit(12).to_string(),
+            "> | a |\n> | - |\n> \n> …"
+        );
+        assert_eq!(
+            formatter.soft_limit(15).to_string(),
+            "> | a |\n> | - |\n> b\n> \n> …"
+        );
+
+        // Rows need a matching delimiter row to start a table.
+        let formatter = Blockquote::new("a | b\n|-|\nc").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(3).to_string(), "> a |…");
+    }
+
+    #[test]
+    fn test_diff_mode() {
+        const INPUT: &str = "@@ -1,4 +1,4 @@\n fn greet() {\n-    println!(\"hi\");\n+    println!(\"hello\");\n \n }\n ";
+        const OUTPUT: &str = "> @@ -1,4 +1,4 @@\n>  fn greet() {\n> -    println!(\"hi\");\n> +    println!(\"hello\");\n>  \n>  }\n>  ";
+
+        let formatter = Blockquote::new(INPUT).diff_mode(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+        assert_eq!(formatter.preserve_hard_breaks(true).to_string(), OUTPUT);
+        assert_eq!(
+            formatter.soft_limit(27).to_string(),
+            "> @@ -1,4 +1,4 @@\n>  fn greet()…"
+        );
+
+        // Markers of context lines aren't dedented or trimmed.
+        const CONTEXT: &str = " \n fn main() {}\n \n fn other() {}\n ";
+
+        let formatter = Blockquote::new(CONTEXT).dedent(true);
+        assert_eq!(formatter.to_string(), "> fn main() {}\n> \n> fn other() {}");
+        assert_eq!(
+            formatter.diff_mode(true).to_string(),
+            ">  \n>  fn main() {}\n>  \n>  fn other() {}\n>  "
+        );
+    }
+
+    #[test]
+    fn test_break_on_list_item() {
+        const INPUT: &str =
+            "Steps:\n- install the crate\n- add it to the manifest\n  with features\n- use it\n\nDone.";
+        const FIRST: &str = "> Steps:\n> - install the crate";
+        const SECOND: &str =
+            "> Steps:\n> - install the crate\n> - add it to the manifest\n>   with features";
+
+        let formatter = Blockquote::new(INPUT).break_on(Boundary::ListItem);
+        assert_eq!(formatter.to_string(), Blockquote::new(INPUT).to_string());
+
+        // Items cut within are dropped, including their continuation lines.
+        assert_eq!(formatter.soft_limit(60).to_string(), format!("{}…", FIRST));
+        assert_eq!(formatter.soft_limit(70).to_string(), format!("{}…", SECOND));
+        assert_eq!(formatter.soft_limit(67).to_string(), format!("{}…", SECOND));
+
+        // The first item of a list is cut as usual.
+        assert_eq!(
+            formatter.soft_limit(16).to_string(),
+            "> Steps:\n> - install…"
+        );
+
+        // Text after a list is cut as usual.
+        assert_eq!(
+            formatter.soft_limit(82).to_string(),
+            format!("{}\n> - use it\n> \n> Done…", SECOND)
+        );
+
+        // Nested items are items too.
+        let formatter = Blockquote::new("1. a\n   - b1\n   - b2 long\n2) c")
+            .break_on(Boundary::ListItem)
+            .soft_limit(19);
+        assert_eq!(formatter.to_string(), "> 1. a\n>    - b1…");
+    }
+
+    #[test]
+    fn test_inline_html_limit() {
+        const INPUT: &str = "a <img alt=\"a > b\" src=\"cat.png\"> c";
+
+        // Tags straddling the limit are cut before entirely, even when the
+        // limit falls in a quoted attribute value.
+        let formatter = Blockquote::new(INPUT).inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(5).to_string(), "> a…");
+        assert_eq!(formatter.soft_limit(13).to_string(), "> a…");
+        assert_eq!(
+            formatter.soft_limit(33).to_string(),
+            "> a <img alt=\"a > b\" src=\"cat.png\">…"
+        );
+
+        // Closing tags are kept whole too.
+        let formatter = Blockquote::new("<details>x</details>").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(12).to_string(), "> <details>x…");
+
+        // Less-than signs that don't start tags are literal.
+        let formatter = Blockquote::new("i <3 u <b").inline_markdown_aware(true);
+        assert_eq!(formatter.soft_limit(3).to_string(), "> i <…");
+        assert_eq!(formatter.soft_limit(8).to_string(), "> i <3 u <…");
+    }
+
+    #[test]
+    fn test_preserve_hard_breaks() {
+        const INPUT: &str = "first  \nsecond \nthird\t\nfourth \t  \n   \nfifth\\\nlast  ";
+        const OUTPUT: &str = "> first  \n> second\n> third\n> fourth  \n> \n> fifth\\\n> last";
+
+        let formatter = Blockquote::new(INPUT).preserve_hard_breaks(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        // Hard line breaks aren't consumed by wraps.
+        let formatter = formatter.wrap(6);
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        // The spaces count toward the limit.
+        let formatter = Blockquote::new(INPUT)
+            .preserve_hard_breaks(true)
+            .soft_limit(8);
+        assert_eq!(formatter.to_string(), "> first  \n> …");
+        let formatter = formatter.soft_limit(6);
+        assert_eq!(formatter.to_string(), "> first…");
+    }
+
+    #[test]
+    fn test_wrap_soft_limit() {
+        const INPUT: &str = "one two three four five six";
+
+        // The soft limit counts the whitespace consumed by wraps, but not the
+        // line breaks inserted in their place.
+        let formatter = Blockquote::new(INPUT).soft_limit(18).wrap(9);
+        assert_eq!(formatter.to_string(), "> one two\n> three\n> four…");
+
+        // Without wrapping lines aren't broken.
+        let formatter = Blockquote::new(INPUT).soft_limit(18);
+        assert_eq!(formatter.to_string(), "> one two three four…");
+    }
+
+    #[test]
+    fn test_start_with_newline() {
+        const OUTPUT: &str = "> test\n> again";
+
+        let formatter = Blockquote::new("\ntest\nagain");
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        let formatter = Blockquote::new("\n \n\ntest\nagain");
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        let formatter = Blockquote::new("\t\n\t\t\ntest\nagain");
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_start_with_indent() {
+        const INPUT: &str = "\n  indented\n  again";
+        const OUTPUT: &str = "> indented\n>   again";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_indent() {
+        const INPUT: &str = "first\n\nsecond";
+
+        let formatter = Blockquote::new(INPUT).indent("  ");
+        assert_eq!(formatter.to_string(), "  > first\n  > \n  > second");
+
+        let formatter = Blockquote::new(INPUT).indent("    ").soft_limit(9);
+        assert_eq!(formatter.to_string(), "    > first\n    > \n    > se…");
+    }
+
+    #[test]
+    fn test_keep_trailing_blank_lines() {
+        let formatter = Blockquote::new("test\nagain\n").keep_trailing_blank_lines(true);
+        assert_eq!(formatter.to_string(), "> test\n> again\n> ");
+
+        let formatter = Blockquote::new("test\nagain\n\n").keep_trailing_blank_lines(true);
+        assert_eq!(formatter.to_string(), "> test\n> again\n> \n> ");
+
+        let formatter = Blockquote::new("test\nagain\n\n")
+            .keep_trailing_blank_lines(true)
+            .soft_limit(11);
+        assert_eq!(formatter.to_string(), "> test\n> again\n> …");
+    }
+
+    #[test]
+    fn test_dedent() {
+        const INPUT: &str = "    first:\n      - second\n    third";
+        const OUTPUT: &str = "> first:\n>   - second\n> third";
+
+        let formatter = Blockquote::new(INPUT).dedent(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        let formatter = Blockquote::new(INPUT).dedent(true).soft_limit(10);
+        assert_eq!(formatter.to_string(), "> first:\n>   -…");
+    }
+
+    #[test]
+    fn test_dedent_mixed() {
+        const INPUT: &str = "\t  first\n\t\tsecond\n  \tthird";
+
+        // Only the common tab is removed, and only from lines starting with it.
+        let formatter = Blockquote::new(&INPUT[..INPUT.len() - 9]).dedent(true);
+        assert_eq!(formatter.to_string(), ">   first\n>     second");
+
+        // No indentation is shared by all three lines. Indenting tabs are
+        // expanded to the tab stops of markdown either way.
+        let formatter = Blockquote::new(INPUT).dedent(true);
+        assert_eq!(
+            formatter.to_string(),
+            ">       first\n>         second\n>     third"
+        );
+    }
+
+    #[test]
+    fn test_dedent_blank_line() {
+        const INPUT: &str = "    first\n\n  \n    second";
+        const OUTPUT: &str = "> first\n> \n> \n> second";
+
+        let formatter = Blockquote::new(INPUT).dedent(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_write_bytes() {
+        const OUTPUT: &str = "> this is a simple test";
+
+        let formatter = Blockquote::new("this is a simple test");
+
+        let mut buf = [0; OUTPUT.len()];
+        assert_eq!(formatter.write_bytes(&mut buf), Ok(OUTPUT.len()));
+        assert_eq!(&buf, OUTPUT.as_bytes());
+
+        let mut buf = [0; OUTPUT.len() - 1];
+        let error = formatter.write_bytes(&mut buf).unwrap_err();
+        assert!(matches!(
+            error,
+            BlockquoteError::Capacity(error) if error.written() == OUTPUT.len() - 1
+        ));
+        assert_eq!(&buf, &OUTPUT.as_bytes()[..OUTPUT.len() - 1]);
+        assert_eq!(
+            error.to_string(),
+            "buffer is too small to hold the blockquote"
+        );
+    }
+
+    #[test]
+    fn test_write_bytes_char_boundary() {
+        const OUTPUT: &str = "> this…";
+
+        let formatter = Blockquote::new("this is a simple test").soft_limit(4);
+
+        // The ellipsis is three bytes long, so none of it fits.
+        let mut buf = [0; OUTPUT.len() - 1];
+        assert_eq!(
+            formatter.write_bytes(&mut buf),
+            Err(BlockquoteError::Capacity(CapacityError { written: 6 }))
+        );
+        assert_eq!(&buf[..6], b"> this");
+        assert_eq!(buf[6..], [0, 0]);
+    }
+
+    #[test]
+    fn test_write_to() {
+        /// Writer failing after accepting a number of bytes.
+        struct Failing {
+            remaining: usize,
+        }
+
+        impl Write for Failing {
+            fn write_str(&mut self, s: &str) -> Result<(), FmtError> {
+                self.remaining = self.remaining.checked_sub(s.len()).ok_or(FmtError)?;
+
+                Ok(())
+            }
+        }
+
+        let formatter = Blockquote::new("this is a simple test").soft_limit(4);
+
+        let mut output = String::from("quote: ");
+        assert_eq!(formatter.write_to(&mut output), Ok(9));
+        assert_eq!(output, "quote: > this…");
+
+        let error = formatter
+            .write_to(&mut Failing { remaining: 4 })
+            .unwrap_err();
+        assert_eq!(error, BlockquoteError::Fmt(FmtError));
+        assert_eq!(error.to_string(), "writer failed to write the blockquote");
+        assert_eq!(formatter.write_to(&mut Failing { remaining: 9 }), Ok(9));
+    }
+
+    #[test]
+    fn test_empty_line_fill() {
+        const INPUT: &str = "first\n\nsecond\n\n\nthird\n";
+
+        let formatter = Blockquote::new(INPUT).empty_line_fill("\u{a0}");
+        assert_eq!(
+            formatter.to_string(),
+            "> first\n> \u{a0}\n> second\n> \u{a0}\n> \u{a0}\n> third"
+        );
+
+        let formatter = Blockquote::new(INPUT)
+            .empty_line_fill("\u{a0}")
+            .keep_trailing_blank_lines(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> first\n> \u{a0}\n> second\n> \u{a0}\n> \u{a0}\n> third\n> \u{a0}"
+        );
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        const INPUT: &str = "\tstart\nmid\tdle\nend\t";
+
+        let formatter = Blockquote::new(INPUT).expand_tabs(4);
+        assert_eq!(formatter.to_string(), ">     start\n> mid dle\n> end");
+
+        let formatter = Blockquote::new(INPUT).expand_tabs(8);
+        assert_eq!(
+            formatter.to_string(),
+            ">         start\n> mid     dle\n> end"
+        );
+    }
+
+    #[test]
+    fn test_expand_tabs_limit() {
+        const INPUT: &str = "ab\tcd";
+
+        // The expanded tab is six spaces wide, and doesn't fit.
+        let formatter = Blockquote::new(INPUT).expand_tabs(8).soft_limit(7);
+        assert_eq!(formatter.to_string(), "> ab…");
+
+        let formatter = Blockquote::new(INPUT).expand_tabs(8).soft_limit(8);
+        assert_eq!(formatter.to_string(), "> ab      …");
+
+        let formatter = Blockquote::new(INPUT).expand_tabs(4).wrap(5);
+        assert_eq!(formatter.to_string(), "> ab\n> cd");
+    }
+
+    #[test]
+    fn test_limit_unit() {
+        const INPUT: &str = "日本語のテキスト";
+
+        let formatter = Blockquote::new(INPUT).soft_limit(4);
+        assert_eq!(formatter.to_string(), "> 日本語の…");
+
+        // Each character is three bytes long.
+        let formatter = formatter.limit_unit(LimitUnit::Bytes);
+        assert_eq!(formatter.to_string(), "> 日…");
+
+        let formatter = formatter.soft_limit(6);
+        assert_eq!(formatter.to_string(), "> 日本…");
+    }
+
+    #[test]
+    fn test_limit_unit_bytes_ascii() {
+        const INPUT: &str = "this text is too long :(";
+        const OUTPUT: &str = "> this text is too lo…";
+
+        let formatter = Blockquote::new(INPUT)
+            .limit_unit(LimitUnit::Bytes)
+            .soft_limit(19);
+
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn test_limit_unit_columns() {
+        // Each character is two columns wide.
+        let formatter = Blockquote::new("日本語のテキスト")
+            .limit_unit(LimitUnit::Columns)
+            .soft_limit(4);
+        assert_eq!(formatter.to_string(), "> 日本…");
+
+        // Combining marks have no width.
+        let formatter = Blockquote::new("e\u{301}e\u{301}e\u{301}e\u{301}")
+            .limit_unit(LimitUnit::Columns)
+            .soft_limit(2);
+        assert_eq!(formatter.to_string(), "> e\u{301}e\u{301}…");
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn test_limit_unit_graphemes() {
+        const INPUT: &str = "e\u{301}e\u{301}e\u{301}e\u{301}";
+
+        let formatter = Blockquote::new(INPUT).soft_limit(2);
+        assert_eq!(formatter.to_string(), "> e\u{301}…");
+        let formatter = formatter.limit_unit(LimitUnit::Graphemes);
+        assert_eq!(formatter.to_string(), "> e\u{301}e\u{301}…");
+        assert_eq!(formatter.soft_limit(4).to_string(), format!("> {}", INPUT));
+
+        // Emoji sequences are a single cluster, and line breaks count.
+        let formatter = Blockquote::new("👩\u{200D}💻 ok\na\u{301}bc")
+            .limit_unit(LimitUnit::Graphemes)
+            .soft_limit(6);
+        assert_eq!(formatter.to_string(), "> 👩\u{200D}💻 ok\n> a\u{301}…");
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        const INPUT: &str = "test\nagain\n\n";
+
+        let formatter = Blockquote::new(INPUT).trailing_newline(true);
+        assert_eq!(formatter.to_string(), "> test\n> again\n");
+
+        let formatter = formatter.soft_limit(7);
+        assert_eq!(formatter.to_string(), "> test\n> ag…\n");
+        assert_eq!(
+            formatter.trailing_newline(false).to_string(),
+            "> test\n> ag…"
+        );
+        assert_eq!(
+            formatter.line_ending(LineEnding::CrLf).to_string(),
+            "> test\r\n> ag…\r\n"
+        );
+
+        let formatter = Blockquote::new(" \n").trailing_newline(true);
+        assert!(formatter.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_truncation_suffix() {
+        const INPUT: &str = "this is just:\na really cool test!";
+
+        let formatter = Blockquote::new(INPUT)
+            .soft_limit(24)
+            .hard_limit(10)
+            .truncation_suffix(" […]");
+        assert_eq!(
+            formatter.to_string(),
+            "> this is just:\n> a really cool […]"
+        );
+
+        let formatter = formatter.truncation_suffix(" [read more](https://example.com)");
+        assert_eq!(
+            formatter.to_string(),
+            "> this is just:\n> a really cool [read more](https://example.com)"
+        );
+
+        let formatter = formatter.with_ellipsis(false);
+        assert_eq!(formatter.to_string(), "> this is just:\n> a really cool");
+
+        let formatter = Blockquote::new("short").truncation_suffix(" […]");
+        assert_eq!(formatter.to_string(), "> short");
+    }
+
+    #[test]
+    fn test_leading_newline() {
+        const INPUT: &str = "\ntest\nagain";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), "> test\n> again");
+
+        let formatter = formatter.leading_newline(true);
+        assert_eq!(formatter.to_string(), "\n> test\n> again");
+
+        let formatter = formatter.trailing_newline(true);
+        assert_eq!(formatter.to_string(), "\n> test\n> again\n");
+
+        let formatter = Blockquote::new("\n\n").leading_newline(true);
+        assert!(formatter.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_from_lines() {
+        const LINES: &[&str] = &["", "this is just:", "", "a really cool test!", "", ""];
+
+        let joined = LINES.join("\n");
+
+        let formatter = Blockquote::from_lines(LINES);
+        assert_eq!(formatter.to_string(), Blockquote::new(&joined).to_string());
+        assert_eq!(
+            formatter.to_string(),
+            "> this is just:\n> \n> a really cool test!"
+        );
+
+        let formatter = formatter.soft_limit(25).hard_limit(10);
+        assert_eq!(
+            formatter.to_string(),
+            Blockquote::new(&joined)
+                .soft_limit(25)
+                .hard_limit(10)
+                .to_string()
+        );
+        assert_eq!(
+            formatter.to_string(),
+            "> this is just:\n> \n> a really cool…"
+        );
+    }
+
+    #[test]
+    fn test_from_lines_empty() {
+        assert!(Blockquote::from_lines(&[]).is_empty());
+        assert!(Blockquote::from_lines(&["", " ", "\t"]).is_empty());
+        assert_eq!(Blockquote::from_lines(&[]).to_string(), "");
+    }
+
+    #[test]
+    fn test_from_lines_newlines() {
+        const LINES: &[&str] = &["first\nsecond", "third"];
+
+        let formatter = Blockquote::from_lines(LINES).dedent(true);
+        assert_eq!(formatter.to_string(), "> first\n> second\n> third");
+    }
+
+    #[test]
+    fn test_crlf() {
+        const OUTPUT: &str = "> test\n> \n> again";
+
+        let formatter = Blockquote::new("\r\ntest\r\n\r\nagain\r\n");
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        let formatter = Blockquote::new("test\r\n\nagain");
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        let formatter = Blockquote::new("test\r\nagain\r\n").keep_trailing_blank_lines(true);
+        assert_eq!(formatter.to_string(), "> test\n> again\n> ");
+
+        let formatter = Blockquote::from_lines(&["test\r", "again\r\n"]);
+        assert_eq!(formatter.to_string(), "> test\n> again");
+
+        let formatter = Blockquote::new("  test\r\n\r\n  again").dedent(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+    }
+
+    #[test]
+    fn test_crlf_limit() {
+        // Line breaks count as a single character.
+        let formatter = Blockquote::new("test\r\nagain").soft_limit(7);
+        assert_eq!(formatter.to_string(), "> test\n> ag…");
+    }
+
+    #[test]
+    fn test_carriage_return() {
+        const INPUT: &str = "test\rtest\nagain\r";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), "> test\rtest\n> again");
+    }
+
+    #[test]
+    fn test_per_line_limit() {
+        const INPUT: &str = "first line\nsecond line\nthird\nfourth line";
+
+        let formatter = Blockquote::new(INPUT).per_line_limit(6);
+        assert_eq!(
+            formatter.to_string(),
+            "> first …\n> second…\n> third\n> fourth…"
+        );
+
+        let formatter = formatter.soft_limit(19);
+        assert_eq!(formatter.to_string(), "> first …\n> second…\n> third…");
+
+        // The line is cut off at the same time as the text.
+        let formatter = formatter.soft_limit(13);
+        assert_eq!(formatter.to_string(), "> first …\n> second…");
+    }
+
+    #[test]
+    fn test_ellipsis_on_new_line() {
+        let formatter = Blockquote::new("first\nsecond line").ellipsis_on_new_line(true);
+        assert_eq!(formatter.to_string(), "> first\n> second line");
+        assert_eq!(formatter.soft_limit(5).to_string(), "> first\n> …");
+        assert_eq!(formatter.soft_limit(8).to_string(), "> first\n> se\n> …");
+        assert_eq!(
+            formatter
+                .ellipsis_on_new_line(false)
+                .soft_limit(5)
+                .to_string(),
+            "> first…"
+        );
+
+        // Lines cut off by the per-line limit keep their ellipsis.
+        let formatter = Blockquote::new("first line\nsecond line\nthird\nfourth line")
+            .per_line_limit(6)
+            .ellipsis_on_new_line(true);
+        assert_eq!(
+            formatter.soft_limit(19).to_string(),
+            "> first …\n> second…\n> third\n> …"
+        );
+        assert_eq!(
+            formatter.soft_limit(13).to_string(),
+            "> first …\n> second\n> …"
+        );
+
+        let formatter = Blockquote::new("**a** b c")
+            .strikethrough(true)
+            .ellipsis_on_new_line(true)
+            .soft_limit(5);
+        assert_eq!(formatter.to_string(), "> ~~**a**~~\n> …");
+    }
+
+    #[test]
+    fn test_line_ending() {
+        const INPUT: &str = "first\nsecond\r\nthird";
+
+        let formatter = Blockquote::new(INPUT)
+            .leading_newline(true)
+            .trailing_newline(true);
+        assert_eq!(formatter.to_string(), "\n> first\n> second\n> third\n");
+
+        let formatter = formatter.line_ending(LineEnding::CrLf);
+        assert_eq!(
+            formatter.to_string(),
+            "\r\n> first\r\n> second\r\n> third\r\n"
+        );
+    }
+
+    #[test]
+    fn test_line_ending_bytes() {
+        const INPUT: &str = "first\nsecond";
+
+        let formatter = Blockquote::new(INPUT)
+            .limit_unit(LimitUnit::Bytes)
+            .soft_limit(8);
+        assert_eq!(formatter.to_string(), "> first\n> se…");
+
+        let formatter = formatter.line_ending(LineEnding::CrLf);
+        assert_eq!(formatter.to_string(), "> first\r\n> s…");
+
+        // Line breaks are a single character regardless of the line ending.
+        let formatter = formatter.limit_unit(LimitUnit::Chars);
+        assert_eq!(formatter.to_string(), "> first\r\n> se…");
+    }
+
+    #[test]
+    fn test_would_truncate_at() {
+        const INPUT: &str = "this is just:\na really cool test!";
+
+        let formatter = Blockquote::new(INPUT);
+
+        for soft in 0..INPUT.len() {
+            let truncated = !formatter.soft_limit(soft).to_string().ends_with("test!");
+
+            assert_eq!(formatter.would_truncate_at(soft, None), truncated);
+        }
+
+        assert!(!formatter.would_truncate_at(INPUT.len(), None));
+        assert!(!formatter.would_truncate_at(usize::MAX, None));
+        assert!(formatter.would_truncate_at(28, None));
+        assert!(!formatter.would_truncate_at(28, Some(5)));
+        assert!(!Blockquote::new("").would_truncate_at(0, None));
+    }
+
+    #[test]
+    fn test_unicode_newlines() {
+        const INPUT: &str = "first\rsecond\u{2028}third\u{2029}fourth";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(
+            formatter.to_string(),
+            "> first\rsecond\u{2028}third\u{2029}fourth"
+        );
+
+        let formatter = formatter.unicode_newlines(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> first\n> second\n> third\n> \n> fourth"
+        );
+
+        let formatter = Blockquote::new("\u{2029}\rfirst\r\nsecond\r\r").unicode_newlines(true);
+        assert_eq!(formatter.to_string(), "> first\n> second");
+    }
+
+    #[test]
+    fn test_unicode_newlines_next_line() {
+        const INPUT: &str = "first\u{85}second";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), "> first\u{85}second");
+
+        let formatter = formatter.unicode_newlines(true);
+        assert_eq!(formatter.to_string(), "> first\n> second");
+        assert_eq!(formatter.soft_limit(6).to_string(), "> first\n> …");
+    }
+
+    #[test]
+    fn test_unicode_newlines_limit() {
+        const INPUT: &str = "first\rsecond\u{2028}third\u{2029}fourth";
+
+        let formatter = Blockquote::new(INPUT).unicode_newlines(true);
+
+        // Line breaks count as single characters, and the paragraph separator
+        // as two line breaks.
+        let expected = [
+            (5, "> first…"),
+            (6, "> first\n> …"),
+            (12, "> first\n> second…"),
+            (13, "> first\n> second\n> …"),
+            (18, "> first\n> second\n> third…"),
+            (19, "> first\n> second\n> third\n> …"),
+            (20, "> first\n> second\n> third\n> \n> …"),
+        ];
+
+        for (limit, output) in expected {
+            assert_eq!(formatter.soft_limit(limit).to_string(), output);
+        }
+    }
+
+    #[test]
+    fn test_nest_existing() {
+        const INPUT: &str = "> first\n>second\n> > third\n>>  fourth\n\n>\nfifth";
+
+        let formatter = Blockquote::new(INPUT).nest_existing(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> > first\n> > second\n> > > third\n> > >  fourth\n> \n> > \n> fifth"
+        );
+
+        // Quote markers don't count toward the limit.
+        let formatter = formatter.soft_limit(15);
+        assert_eq!(formatter.to_string(), "> > first\n> > second\n> > > th…");
+    }
+
+    #[test]
+    fn test_nest_existing_mid_line() {
+        const INPUT: &str = "a -> b\n    > code\n  > c > d";
+
+        let formatter = Blockquote::new(INPUT).nest_existing(true);
+        assert_eq!(formatter.to_string(), "> a -> b\n>     > code\n> > c > d");
+
+        // Nesting takes precedence over escaping.
+        let formatter = formatter.escape_existing_quotes(true);
+        assert_eq!(formatter.to_string(), "> a -> b\n>     > code\n> > c > d");
+    }
+
+    #[test]
+    fn test_bidi_isolate() {
+        const INPUT: &str = "انظر https://example.com للمزيد\nhttps://example.org";
+
+        let formatter = Blockquote::new(INPUT).bidi_isolate(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> \u{2068}انظر https://example.com للمزيد\u{2069}\n> \u{2068}https://example.org\u{2069}"
+        );
+
+        // Isolates don't count toward the limit, and the ellipsis is isolated.
+        let formatter = formatter.soft_limit(24);
+        assert_eq!(
+            formatter.to_string(),
+            "> \u{2068}انظر https://example.com…\u{2069}"
+        );
+    }
+
+    #[test]
+    fn test_control_chars() {
+        const INPUT: &str = "null\0 and \x1B[0mreset\tok\r\nbell\x07\x7F";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(
+            formatter.to_string(),
+            "> null\0 and \x1B[0mreset\tok\n> bell\x07\x7F"
+        );
+
+        let formatter = formatter.control_chars(ControlPolicy::Escape);
+        assert_eq!(
+            formatter.to_string(),
+            "> null\\x00 and \\x1B[0mreset\tok\n> bell\\x07\\x7F"
+        );
+
+        let formatter = formatter.control_chars(ControlPolicy::Strip);
+        assert_eq!(formatter.to_string(), "> null and [0mreset\tok\n> bell");
+
+        let formatter = Blockquote::new("\0\n\x1B").control_chars(ControlPolicy::Strip);
+        assert!(formatter.is_empty());
+    }
+
+    #[test]
+    fn test_control_chars_limit() {
+        const INPUT: &str = "abc\0de\x1Bfgh";
+
+        // Escapes count toward the limits, and aren't split.
+        let formatter = Blockquote::new(INPUT).control_chars(ControlPolicy::Escape);
+        assert_eq!(formatter.soft_limit(5).hard_limit(0).to_string(), "> abc…");
+        assert_eq!(
+            formatter.soft_limit(7).hard_limit(0).to_string(),
+            "> abc\\x00…"
+        );
+        assert_eq!(
+            formatter.soft_limit(13).hard_limit(0).to_string(),
+            "> abc\\x00de\\x1B…"
+        );
+
+        // Removed controls don't count toward the limits.
+        let formatter = Blockquote::new(INPUT).control_chars(ControlPolicy::Strip);
+        assert_eq!(formatter.soft_limit(6).to_string(), "> abcdef…");
+    }
+
+    #[test]
+    fn test_sanitize_bidi() {
+        const INPUT: &str = "see \u{202E}gnp.exe and \u{2067}more";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), format!("> {}", INPUT));
+
+        let formatter = formatter.sanitize_bidi(BidiPolicy::Strip);
+        assert_eq!(formatter.to_string(), "> see gnp.exe and more");
+        assert_eq!(formatter.soft_limit(7).to_string(), "> see gnp…");
+
+        let formatter = formatter.sanitize_bidi(BidiPolicy::Escape);
+        assert_eq!(
+            formatter.to_string(),
+            "> see \\u{202E}gnp.exe and \\u{2067}more"
+        );
+        assert_eq!(formatter.soft_limit(12).to_string(), "> see \\u{202E}…");
+
+        // Only the formatting left open at the end of each line is closed.
+        let formatter =
+            Blockquote::new("a\u{202E}b\u{2067}c\u{202B}d\u{2069}e\nf\u{202D}g\u{202C}h")
+                .sanitize_bidi(BidiPolicy::Close);
+        assert_eq!(
+            formatter.to_string(),
+            "> a\u{202E}b\u{2067}c\u{202B}d\u{2069}e\u{202C}\n> f\u{202D}g\u{202C}h"
+        );
+
+        let formatter = Blockquote::new("\u{202E}\n\u{2066}").sanitize_bidi(BidiPolicy::Strip);
+        assert!(formatter.is_empty());
+        assert!(!formatter.sanitize_bidi(BidiPolicy::Keep).is_empty());
+
+        let formatter = Blockquote::new(INPUT)
+            .sanitize_bidi(BidiPolicy::Strip)
+            .sanitize_bidi(BidiPolicy::Keep);
+        assert_eq!(formatter, Blockquote::new(INPUT));
+    }
+
+    #[test]
+    fn test_sanitize_bidi_truncated() {
+        const INPUT: &str = "see \u{2067}this is cool\u{2069} ok";
+
+        // The isolate is open where the text is cut off.
+        let formatter = Blockquote::new(INPUT)
+            .soft_limit(9)
+            .sanitize_bidi(BidiPolicy::Close);
+        assert_eq!(formatter.to_string(), "> see \u{2067}this…\u{2069}");
+
+        let formatter = formatter.bidi_isolate(true).strikethrough(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> \u{2068}~~see \u{2067}this…\u{2069}~~\u{2069}"
+        );
+
+        // Removed controls don't count toward the limit.
+        let formatter = formatter.sanitize_bidi(BidiPolicy::Strip);
+        assert_eq!(formatter.to_string(), "> \u{2068}~~see this …~~\u{2069}");
+        assert_eq!(
+            formatter.to_string(),
+            Blockquote::new("see this is cool ok")
+                .soft_limit(9)
+                .bidi_isolate(true)
+                .strikethrough(true)
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_ellipsis_replaces() {
+        const INPUT: &str = "this is just:\na really cool test!";
+
+        let formatter = Blockquote::new(INPUT).ellipsis_replaces(true);
+
+        for soft_limit in 0..=INPUT.len() {
+            let output = formatter.soft_limit(soft_limit).to_string();
+            let chars = output.replace("> ", "").chars().count();
+
+            assert!(chars <= soft_limit, "{} > {}", chars, soft_limit);
+        }
+
+        assert_eq!(
+            formatter.soft_limit(INPUT.len()).to_string(),
+            "> this is just:\n> a really cool test!"
+        );
+        assert_eq!(
+            formatter.soft_limit(INPUT.len() - 1).to_string(),
+            "> this is just:\n> a really cool tes…"
+        );
+    }
+
+    #[test]
+    fn test_ellipsis_replaces_suffix() {
+        const INPUT: &str = "this text is too long";
+
+        let formatter = Blockquote::new(INPUT)
+            .ellipsis_replaces(true)
+            .truncation_suffix(" […]")
+            .soft_limit(13);
+        assert_eq!(formatter.to_string(), "> this text […]");
+
+        let formatter = formatter.limit_unit(LimitUnit::Bytes);
+        assert_eq!(formatter.to_string(), "> this te […]");
+    }
+
+    #[test]
+    fn test_escape_existing_quotes() {
+        const INPUT: &str = "> quoted\n  >> nested\nvalue -> other\n    > code";
+
+        let formatter = Blockquote::new(INPUT).escape_existing_quotes(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> \\> quoted\n>   \\>> nested\n> value -> other\n>     > code"
+        );
+
+        // Backslashes count toward the limit.
+        let formatter = formatter.soft_limit(4);
+        assert_eq!(formatter.to_string(), "> \\> q…");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_from_iter() {
+        use super::BlockquoteBuf;
+        use core::iter::FromIterator;
+
+        const LINES: [&str; 4] = ["first", "", "second\nthird", "fourth"];
+
+        let formatter = BlockquoteBuf::from_iter(LINES.iter().copied());
+        assert_eq!(
+            formatter.to_string(),
+            Blockquote::from_lines(&LINES).to_string()
+        );
+        assert_eq!(formatter.as_str(), "first\n\nsecond\nthird\nfourth");
+
+        let formatter = formatter.soft_limit(13);
+        assert_eq!(formatter.to_string(), "> first\n> \n> second…");
+
+        let formatter = core::iter::empty().collect::<BlockquoteBuf>();
+        assert!(formatter.is_empty());
+        assert_eq!(formatter.to_string(), "");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_map_lines() {
+        const INPUT: &str = "token: abc123\r\n\nuse token abc123 wisely";
+
+        let formatter = Blockquote::new(INPUT).soft_limit(15);
+        let mapped = formatter.map_lines(|line| line.replace("abc123", "***"));
+        assert_eq!(mapped.as_str(), "token: ***\n\nuse token *** wisely");
+        assert_eq!(mapped.to_string(), "> token: ***\n> \n> use…");
+
+        // Lines may be borrowed, and split into more lines.
+        let mapped = formatter.map_lines(|line| if line.is_empty() { "-\n-" } else { "x" });
+        assert_eq!(mapped.to_string(), "> x\n> -\n> -\n> x");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_to_owned() {
+        use super::BlockquoteBuf;
+
+        const INPUT: &str = "first line\n\n  second line with more words";
+
+        let formatter = Blockquote::new(INPUT)
+            .soft_limit(20)
+            .indent("  ")
+            .empty_line_fill("\u{a0}")
+            .truncation_suffix(" [...]");
+        let owned = formatter.to_owned();
+        assert_eq!(owned.to_string(), formatter.to_string());
+        assert_eq!(owned.as_blockquote(), formatter);
+
+        let built = BlockquoteBuf::new(INPUT)
+            .soft_limit(20)
+            .indent("  ")
+            .empty_line_fill("\u{a0}")
+            .truncation_suffix(" [...]");
+        assert_eq!(built, owned);
+
+        let formatter = Blockquote::new(INPUT).alert(AlertKind::Custom("DANGER"));
+        assert_eq!(formatter.to_owned().to_string(), formatter.to_string());
+        assert_eq!(
+            BlockquoteBuf::new(INPUT).alert(AlertKind::Custom("DANGER")),
+            formatter.to_owned()
+        );
+
+        // Lines are joined by newlines.
+        let lines = ["first line", "", "  second line with more words"];
+        let formatter = Blockquote::from_lines(&lines).dedent(true);
+        let owned = formatter.to_owned();
+        assert_eq!(owned.as_str(), INPUT);
+        assert_eq!(owned.to_string(), formatter.to_string());
+    }
+
+    #[test]
+    fn test_min_chars() {
+        const INPUT: &str = "hey, this is cool!";
+
+        let formatter = Blockquote::new(INPUT).min_chars(5);
+        assert_eq!(formatter.to_string(), "> hey, this is cool!");
+        assert_eq!(formatter.soft_limit(5).to_string(), "> hey, …");
+        assert_eq!(formatter.soft_limit(4).to_string(), "");
+        assert_eq!(formatter.soft_limit(2).hard_limit(1).to_string(), "");
+        assert_eq!(formatter.max_bytes(6).to_string(), "");
+        assert_eq!(formatter.soft_limit(2).emitted_char_count(), 0);
+        assert!(!formatter.soft_limit(5).is_empty());
+        assert!(formatter.soft_limit(4).is_empty());
+        assert!(formatter.max_bytes(6).is_empty());
+
+        // Short text that isn't cut off is formatted.
+        let formatter = Blockquote::new("hey").min_chars(5).soft_limit(3);
+        assert_eq!(formatter.to_string(), "> hey");
+    }
+
+    #[test]
+    fn test_min_chars_unlimited() {
+        const INPUT: &str = "hey, this is cool!";
+
+        let formatter = Blockquote::new(INPUT)
+            .min_chars(5)
+            .min_chars_policy(MinCharsPolicy::Unlimited);
+        assert_eq!(formatter.soft_limit(5).to_string(), "> hey, …");
+        assert_eq!(formatter.soft_limit(4).to_string(), "> hey, this is cool!");
+        assert_eq!(formatter.max_bytes(6).to_string(), "> hey, this is cool!");
+        assert!(!formatter.soft_limit(4).is_empty());
+    }
+
+    #[test]
+    fn test_squash_whitespace() {
+        const INPUT: &str = "This is one.  This is two.\nmid{}line\n    indented  \u{a0}\u{a0}text";
+
+        let input = INPUT.replace("{}", &" ".repeat(20));
+        let formatter = Blockquote::new(&input).squash_whitespace(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> This is one. This is two.\n> mid line\n>     indented \u{a0}\u{a0}text"
+        );
+
+        // Squashed whitespace counts as a single space toward the limits.
+        assert_eq!(
+            formatter.soft_limit(34).to_string(),
+            "> This is one. This is two.\n> mid line…"
+        );
+
+        // Tabs are squashed along with spaces.
+        let formatter = Blockquote::new("a \t b\tc\n\tindented").squash_whitespace(true);
+        assert_eq!(formatter.to_string(), "> a b c\n>     indented");
+    }
+
+    #[test]
+    fn test_strikethrough() {
+        const INPUT: &str = "first line\n\n  second line  \n   \nthird";
+        const OUTPUT: &str = "> ~~first line~~\n> \n>   ~~second line~~  \n>    \n> ~~third~~";
+
+        let formatter = Blockquote::new(INPUT).strikethrough(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+
+        // The ellipsis is struck through with the content.
+        let formatter = formatter.soft_limit(20);
+        assert_eq!(
+            formatter.to_string(),
+            "> ~~first line~~\n> \n>   ~~second…~~"
+        );
+
+        let formatter = Blockquote::new("one two three")
+            .strikethrough(true)
+            .bidi_isolate(true)
+            .wrap(7);
+        assert_eq!(
+            formatter.to_string(),
+            "> \u{2068}~~one two~~\u{2069}\n> \u{2068}~~three~~\u{2069}"
+        );
+    }
+
+    #[test]
+    fn test_max_bytes() {
+        const INPUT: &str = "👍👍 great 👍👍\nnext line";
+
+        // A character limit and a byte limit diverge on multi-byte characters.
+        let formatter = Blockquote::new(INPUT).soft_limit(2);
+        assert_eq!(formatter.to_string(), "> 👍👍…");
+        let formatter = Blockquote::new(INPUT).max_bytes(13);
+        assert_eq!(formatter.to_string(), "> 👍👍…");
+
+        // Prefixes, line breaks, and the ellipsis count toward the maximum.
+        for max_bytes in 0..=40 {
+            let formatted = Blockquote::new(INPUT).max_bytes(max_bytes).to_string();
+            assert!(formatted.len() <= max_bytes);
+        }
+
+        let formatter = Blockquote::new(INPUT).max_bytes(12);
+        assert_eq!(formatter.to_string(), "> 👍…");
+        let formatter = Blockquote::new(INPUT).max_bytes(5);
+        assert_eq!(formatter.to_string(), "> …");
+        let formatter = Blockquote::new(INPUT).max_bytes(4);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.would_truncate_at(usize::MAX, None));
+
+        let formatter = Blockquote::new(INPUT).max_bytes(37);
+        assert_eq!(formatter.to_string(), "> 👍👍 great 👍👍\n> next line");
+        assert!(!formatter.would_truncate_at(usize::MAX, None));
+        let formatter = Blockquote::new(INPUT).max_bytes(35);
+        assert_eq!(formatter.to_string(), "> 👍👍 great 👍👍\n> next…");
+    }
+
+    #[test]
+    fn test_emitted_char_count() {
+        const INPUT: &str = "héllo wörld\r\n\n  second line";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.emitted_char_count(), INPUT.chars().count());
+        assert_eq!(
+            formatter.dedent(true).emitted_char_count(),
+            INPUT.chars().count()
+        );
+
+        let formatter = formatter.soft_limit(3);
+        assert_eq!(formatter.to_string(), "> hél…");
+        assert_eq!(formatter.emitted_char_count(), 3);
+
+        let formatter = formatter.soft_limit(16);
+        assert_eq!(formatter.to_string(), "> héllo wörld\n> \n>   s…");
+
+        // Carriage returns are characters of the text despite not counting
+        // toward the limit.
+        assert_eq!(formatter.emitted_char_count(), 17);
+
+        let lines = ["héllo wörld\r", "", "  second line"];
+        let formatter = Blockquote::from_lines(&lines);
+        assert_eq!(formatter.emitted_char_count(), INPUT.chars().count());
+        assert_eq!(formatter.soft_limit(16).emitted_char_count(), 17);
+
+        assert_eq!(Blockquote::new("").emitted_char_count(), 0);
+        assert_eq!(Blockquote::new(" \n").emitted_char_count(), 2);
+    }
+
+    #[test]
+    fn test_trim_leading_blank_lines() {
+        const INPUT: &str = "\n\n  hello";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), "> hello");
+        assert_eq!(formatter.dedent(true).to_string(), "> hello");
+
+        // Text not starting with blank lines keeps its indentation.
+        assert_eq!(
+            Blockquote::new("  hello\n  again").to_string(),
+            ">   hello\n>   again"
+        );
+
+        let formatter = formatter.trim_leading_blank_lines(false);
+        assert_eq!(formatter.to_string(), "> \n> \n>   hello");
+        assert_eq!(formatter.dedent(true).to_string(), "> \n> \n> hello");
+
+        // Leading blank lines count toward the limit.
+        assert_eq!(formatter.soft_limit(3).to_string(), "> \n> \n>  …");
+
+        let formatter = Blockquote::new(" \n\t\n").trim_leading_blank_lines(false);
+        assert!(formatter.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_hard_limit_only() {
+        const INPUT: &str = "this is a long sentence";
+
+        let formatter = Blockquote::new(INPUT).hard_limit_only(12);
+        assert_eq!(formatter.to_string(), "> this is a lo…");
+        assert!(formatter.would_truncate_at(usize::MAX, None));
+
+        let formatter = Blockquote::new(INPUT).hard_limit_only(23);
+        assert_eq!(formatter.to_string(), "> this is a long sentence");
+
+        // Whichever limit is reached first applies.
+        let formatter = Blockquote::new(INPUT)
+            .soft_limit(6)
+            .hard_limit(10)
+            .hard_limit_only(12);
+        assert_eq!(formatter.to_string(), "> this is…");
+        let formatter = Blockquote::new(INPUT)
+            .soft_limit(11)
+            .hard_limit(10)
+            .hard_limit_only(12);
+        assert_eq!(formatter.to_string(), "> this is a lo…");
+
+        let formatter = Blockquote::new(INPUT)
+            .hard_limit_only(12)
+            .ellipsis_replaces(true);
+        assert_eq!(formatter.to_string(), "> this is a l…");
+    }
+
+    #[test]
+    fn test_flatten_nested() {
+        const INPUT: &str = "unquoted\n> one\n>> two\n > > >three\n>\nlast";
+        const OUTPUT: &str = "> unquoted\n> one\n> two\n> three\n> \n> last";
+
+        let formatter = Blockquote::new(INPUT).flatten_nested(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+        assert_eq!(formatter.nest_existing(true).to_string(), OUTPUT);
+
+        // Removed quote markers don't count toward the limits.
+        let formatter = formatter.soft_limit(19);
+        assert_eq!(formatter.to_string(), "> unquoted\n> one\n> two\n> th…");
+    }
+
+    #[test]
+    fn test_flatten_nested_blank() {
+        // Lines left empty by flattening are blank.
+        let formatter = Blockquote::new(">").flatten_nested(true);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.is_empty());
+
+        let formatter = Blockquote::new("a\n> b\n>> > c\n>").flatten_nested(true);
+        assert_eq!(formatter.to_string(), "> a\n> b\n> c");
+        let formatter = Blockquote::new(">\n\n> a\n>\n").flatten_nested(true);
+        assert_eq!(formatter.to_string(), "> a");
+
+        // ANSI escape sequences are stripped before markers are flattened.
+        let formatter = Blockquote::new(">\x1b[31m")
+            .flatten_nested(true)
+            .strip_ansi(true);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.is_empty());
+
+        let formatter = Blockquote::new("\x1b[31m>")
+            .flatten_nested(true)
+            .strip_ansi(true);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.is_empty());
+
+        let formatter = Blockquote::new("\x1b[31m> a\n> \x1b[1m> b\x1b[0m")
+            .flatten_nested(true)
+            .strip_ansi(true);
+        assert_eq!(formatter.to_string(), "> a\n> b");
+    }
+
+    #[test]
+    fn test_close_code_fences() {
+        const INPUT: &str = "Example:\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```\nafter";
+
+        let formatter = Blockquote::new(INPUT)
+            .soft_limit(29)
+            .close_code_fences(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> Example:\n> ```rust\n> fn main() {\n> ```\n> …"
+        );
+
+        // Cut in the middle of a line.
+        let formatter = formatter.soft_limit(22);
+        assert_eq!(
+            formatter.to_string(),
+            "> Example:\n> ```rust\n> fn ma\n> ```\n> …"
+        );
+
+        // Truncation after the block is formatted as usual, and truncation
+        // within its marker cuts before the whole marker.
+        let formatter = formatter.soft_limit(56);
+        assert_eq!(
+            formatter.to_string(),
+            "> Example:\n> ```rust\n> fn main() {\n>     println!(\"hi\");\n> }\n> ```\n> a…"
+        );
+        let formatter = formatter.soft_limit(10);
+        assert_eq!(formatter.to_string(), "> Example:\n> …");
+        let formatter = formatter.soft_limit(11);
+        assert_eq!(formatter.to_string(), "> Example:\n> …");
+
+        // Fences aren't closed without truncation or when disabled.
+        let formatter = Blockquote::new("~~~~\ncode\n~~~\nmore");
+        assert_eq!(
+            formatter.close_code_fences(true).to_string(),
+            "> ~~~~\n> code\n> ~~~\n> more"
+        );
+        assert_eq!(formatter.soft_limit(11).to_string(), "> ~~~~\n> code\n> ~…");
+        assert_eq!(
+            formatter.soft_limit(11).close_code_fences(true).to_string(),
+            "> ~~~~\n> code\n> ~~~~\n> …"
+        );
+
+        // Inline markdown awareness closes fences too, including those cut
+        // in their info string.
+        let formatter = Blockquote::new("~~~~ rust\nlet a = 1;\n~~~~").inline_markdown_aware(true);
+        assert_eq!(
+            formatter.soft_limit(11).to_string(),
+            "> ~~~~ rust\n> l\n> ~~~~\n> …"
+        );
+        assert_eq!(
+            formatter.soft_limit(7).to_string(),
+            "> ~~~~ ru\n> ~~~~\n> …"
+        );
+        assert_eq!(formatter.soft_limit(3).to_string(), "> …");
+    }
+
+    #[test]
+    fn test_close_code_fences_skipped_marker() {
+        // The rest of a marker cut off by the per-line limit is skipped.
+        let formatter = Blockquote::new("```\nx")
+            .per_line_limit(1)
+            .close_code_fences(true);
+        assert_eq!(formatter.to_string(), "> `…\n> x");
+        assert_eq!(
+            Blockquote::new("```\nx\ny")
+                .per_line_limit(1)
+                .soft_limit(3)
+                .close_code_fences(true)
+                .to_string(),
+            "> `…\n> x\n> ```\n> …"
+        );
+
+        let formatter = Blockquote::new("- a\n```\nb")
+            .per_line_limit(0)
+            .inline_markdown_aware(true);
+        assert_eq!(formatter.to_string(), "> …\n> …\n> …");
+
+        // Indentation skipped before a marker isn't part of it.
+        let formatter = Blockquote::new("  ~~~")
+            .close_code_fences(true)
+            .soft_limit(2);
+        assert_eq!(formatter.to_string(), "> …");
+        let formatter = Blockquote::new("a\n  ~~~\nb")
+            .close_code_fences(true)
+            .soft_limit(3);
+        assert_eq!(formatter.to_string(), "> a\n> …");
+    }
+
+    #[test]
+    fn test_alert() {
+        const INPUT: &str = "back up\nyour data";
+
+        let formatter = Blockquote::new(INPUT).alert(AlertKind::Note);
+        assert_eq!(formatter.to_string(), "> [!NOTE]\n> back up\n> your data");
+
+        // The alert marker doesn't count toward the limits.
+        let formatter = formatter.alert(AlertKind::Caution).soft_limit(7);
+        assert_eq!(formatter.to_string(), "> [!CAUTION]\n> back up…");
+
+        let formatter = formatter
+            .alert(AlertKind::Custom("DANGER"))
+            .trailing_newline(true);
+        assert_eq!(formatter.to_string(), "> [!DANGER]\n> back up…\n");
+
+        let formatter = Blockquote::new(" \n").alert(AlertKind::Note);
+        assert!(formatter.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_attribution() {
+        const INPUT: &str = "first line\nsecond line";
+
+        let formatter = Blockquote::new(INPUT).attribution("Alice");
+        assert_eq!(
+            formatter.to_string(),
+            "> first line\n> second line\n> \n> — Alice"
+        );
+
+        // The attribution is written after truncated text.
+        let formatter = formatter.soft_limit(5);
+        assert_eq!(formatter.to_string(), "> first…\n> \n> — Alice");
+
+        let formatter = formatter
+            .attribution_link("Alice", "https://example.com/1")
+            .trailing_newline(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> first…\n> \n> — [Alice](https://example.com/1)\n"
+        );
+
+        let formatter = Blockquote::new("\n\t").attribution("Alice");
+        assert!(formatter.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_hash() {
+        extern crate std;
+
+        use std::collections::HashSet;
+
+        let lines = ["hello", "world"];
+        let mut set = HashSet::new();
+        assert!(set.insert(Blockquote::new("hello\nworld")));
+        assert!(!set.insert(Blockquote::new("hello\nworld")));
+        assert!(set.insert(Blockquote::new("hello\nworld").soft_limit(5)));
+        assert!(!set.insert(Blockquote::new("hello\nworld").soft_limit(5)));
+
+        // Blockquotes formatting identically may be configured differently.
+        assert!(set.insert(Blockquote::from_lines(&lines)));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn test_header() {
+        const INPUT: &str = "first line\nsecond line\nthird line";
+
+        let formatter = Blockquote::new(INPUT)
+            .header("**alice said:**")
+            .soft_limit(17);
+        assert_eq!(
+            formatter.to_string(),
+            "> **alice said:**\n> first line\n> second…"
+        );
+
+        let formatter = formatter.alert(AlertKind::Tip);
+        assert_eq!(
+            formatter.to_string(),
+            "> [!TIP]\n> **alice said:**\n> first line\n> second…"
+        );
+
+        let formatter = Blockquote::new("").header("**alice said:**");
+        assert!(formatter.to_string().is_empty());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        const INPUT: &str = "\nhello there\n\ngeneral kenobi";
+
+        let formatter = Blockquote::new(INPUT).timestamp("[12:01]");
+        assert_eq!(
+            formatter.to_string(),
+            "> [12:01] hello there\n> \n> general kenobi"
+        );
+
+        let formatter = formatter.timestamp_every_line(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> [12:01] hello there\n> \n> [12:01] general kenobi"
+        );
+
+        // The timestamp doesn't count toward the limits or the wrap width.
+        assert_eq!(formatter.soft_limit(5).to_string(), "> [12:01] hello…");
+        assert_eq!(
+            formatter.soft_limit(20).to_string(),
+            "> [12:01] hello there\n> \n> [12:01] general…"
+        );
+        assert_eq!(
+            formatter.wrap(7).to_string(),
+            "> [12:01] hello\n> there\n> \n> [12:01] general\n> kenobi"
+        );
+
+        // Timestamps ending with whitespace aren't followed by another space.
+        let formatter = Blockquote::new("a").timestamp("[12:01] ");
+        assert_eq!(formatter.to_string(), "> [12:01] a");
+        assert_eq!(formatter.timestamp("12:01\t").to_string(), "> 12:01\ta");
+    }
+
+    #[test]
+    fn test_headings() {
+        const ATX: &str =
+            "# one\n## two\n### three\n#### four\n##### five\n###### six\n####### seven";
+        const SETEXT: &str = "Title\n=====\nSub\n  ---  \nbody\n\n---";
+
+        let formatter = Blockquote::new(ATX);
+        assert_eq!(
+            formatter.headings(HeadingPolicy::Keep).to_string(),
+            formatter.to_string()
+        );
+        assert_eq!(
+            formatter.headings(HeadingPolicy::Strip).to_string(),
+            "> one\n> two\n> three\n> four\n> five\n> six\n> ####### seven"
+        );
+        assert_eq!(
+            formatter.headings(HeadingPolicy::Demote(1)).to_string(),
+            "> ## one\n> ### two\n> #### three\n> ##### four\n> ###### five\n> ###### six\n> ####### seven"
+        );
+        assert_eq!(
+            formatter.headings(HeadingPolicy::Demote(0)).to_string(),
+            formatter.to_string()
+        );
+
+        let formatter = Blockquote::new(SETEXT);
+        assert_eq!(
+            formatter.headings(HeadingPolicy::Keep).to_string(),
+            "> Title\n> =====\n> Sub\n>   ---  \n> body\n> \n> ---"
+        );
+        assert_eq!(
+            formatter.headings(HeadingPolicy::Strip).to_string(),
+            "> Title\n> Sub\n> body\n> \n> ---"
+        );
+        assert_eq!(
+            formatter.headings(HeadingPolicy::Demote(1)).to_string(),
+            "> ## Title\n> ### Sub\n> body\n> \n> ---"
+        );
+
+        // Markers and underlines don't count toward the limits.
+        let formatter = formatter.headings(HeadingPolicy::Demote(5)).soft_limit(9);
+        assert_eq!(formatter.to_string(), "> ###### Title\n> ###### Sub…");
+
+        // Headings need whitespace after their markers, and at most three
+        // spaces of indentation.
+        let formatter = Blockquote::new("   ## a\n    ## b\n#c").headings(HeadingPolicy::Strip);
+        assert_eq!(formatter.to_string(), "> a\n>     ## b\n> #c");
+
+        // Setext headings spanning several lines are left as is.
+        let formatter = Blockquote::new("a\nb\n---\n\nc\n===").headings(HeadingPolicy::Demote(1));
+        assert_eq!(formatter.to_string(), "> a\n> b\n> ---\n> \n> ## c");
+        let formatter = formatter.headings(HeadingPolicy::Strip);
+        assert_eq!(formatter.to_string(), "> a\n> b\n> ---\n> \n> c");
+    }
+
+    #[test]
+    fn test_headings_strip_empty() {
+        // Lines with nothing but a stripped heading marker are blank.
+        let formatter = Blockquote::new("###   ").headings(HeadingPolicy::Strip);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.is_empty());
+        assert!(!formatter.headings(HeadingPolicy::Keep).is_empty());
+
+        let formatter = Blockquote::new("#\na\n## \n").headings(HeadingPolicy::Strip);
+        assert_eq!(formatter.to_string(), "> a");
+
+        for input in ["#", "# \n\n# ", "#\n```\n#", "a\n#", "#a"] {
+            let formatter = Blockquote::new(input).headings(HeadingPolicy::Strip);
+            assert_eq!(
+                formatter.is_empty(),
+                formatter.to_string().is_empty(),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_images() {
+        let formatter = Blockquote::new("see ![a cat](cat.png) here");
+        assert_eq!(
+            formatter.images(ImagePolicy::Keep).to_string(),
+            "> see ![a cat](cat.png) here"
+        );
+        assert_eq!(
+            formatter.images(ImagePolicy::AltText).to_string(),
+            "> see a cat here"
+        );
+        assert_eq!(
+            formatter.images(ImagePolicy::Remove).to_string(),
+            "> see here"
+        );
+
+        // Lines with only images are removed entirely when removing images.
+        let formatter = Blockquote::new("before\n![diagram](d.svg)\nafter");
+        assert_eq!(
+            formatter.images(ImagePolicy::AltText).to_string(),
+            "> before\n> diagram\n> after"
+        );
+        assert_eq!(
+            formatter.images(ImagePolicy::Remove).to_string(),
+            "> before\n> after"
+        );
+        let formatter = Blockquote::new("![d](d.svg)\nx\n![e](e.svg) ![f](f.svg)\n![g](g.svg)\ny")
+            .images(ImagePolicy::Remove);
+        assert_eq!(formatter.to_string(), "> x\n> y");
+        let formatter = Blockquote::new("![d](d.svg)").images(ImagePolicy::Remove);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.is_empty());
+
+        // Whitespace before images ending a line isn't left trailing.
+        let formatter =
+            Blockquote::new("text ![a](b)\nmore ![c](d) ![e](f)\nend").images(ImagePolicy::Remove);
+        assert_eq!(formatter.to_string(), "> text\n> more\n> end");
+        let formatter = Blockquote::new("text ![a](b)").images(ImagePolicy::Remove);
+        assert_eq!(formatter.to_string(), "> text");
+
+        // Images without alt text are replaced by a placeholder.
+        let formatter = Blockquote::new("x ![](a.png) y");
+        assert_eq!(
+            formatter.images(ImagePolicy::AltText).to_string(),
+            "> x [image] y"
+        );
+        assert_eq!(formatter.images(ImagePolicy::Remove).to_string(), "> x y");
+
+        let formatter = Blockquote::new("a ![logo][l] b").images(ImagePolicy::AltText);
+        assert_eq!(formatter.to_string(), "> a logo b");
+
+        // Images in code are kept.
+        let formatter = Blockquote::new("`![a](b)`\n```\n![a](b)\n```");
+        assert_eq!(
+            formatter.images(ImagePolicy::Remove).to_string(),
+            "> `![a](b)`\n> ```\n> ![a](b)\n> ```"
+        );
+
+        // Only alt text counts toward the limits.
+        let formatter = Blockquote::new("see ![a cat](cat.png) here").images(ImagePolicy::AltText);
+        assert_eq!(formatter.soft_limit(9).to_string(), "> see a cat…");
+        assert_eq!(
+            Blockquote::new("x ![](a.png) y")
+                .images(ImagePolicy::AltText)
+                .soft_limit(5)
+                .to_string(),
+            "> x …"
+        );
+    }
+
+    #[test]
+    fn test_collapsible() {
+        let formatter = Blockquote::new("a\nb").collapsible("quote");
+        assert_eq!(
+            formatter.to_string(),
+            "> <details><summary>quote</summary>\n> \n> a\n> b\n> </details>"
+        );
+        assert_eq!(
+            formatter.header("h").attribution("c").to_string(),
+            "> h\n> <details><summary>quote</summary>\n> \n> a\n> b\n> </details>\n> \n> — c"
+        );
+
+        // Only the inner text counts toward the limits.
+        let formatter = Blockquote::new("hey, this is cool!").collapsible("alice");
+        assert_eq!(
+            formatter.soft_limit(9).to_string(),
+            "> <details><summary>alice</summary>\n> \n> hey, this…\n> </details>"
+        );
+        assert_eq!(
+            formatter
+                .line_ending(LineEnding::CrLf)
+                .soft_limit(4)
+                .to_string(),
+            "> <details><summary>alice</summary>\r\n> \r\n> hey,…\r\n> </details>"
+        );
+        assert_eq!(Blockquote::new(" ").collapsible("alice").to_string(), "");
+    }
+
+    #[test]
+    fn test_fenced() {
+        let formatter = Blockquote::new("a\n```\nb\n```").fenced(Some("md"));
+        assert_eq!(
+            formatter.to_string(),
+            "> ````md\n> a\n> ```\n> b\n> ```\n> ````"
+        );
+        assert_eq!(
+            formatter.soft_limit(5).to_string(),
+            "> ````md\n> a\n> ```\n> ````\n> …"
+        );
+
+        let formatter = Blockquote::new("a `` b").fenced(None);
+        assert_eq!(formatter.to_string(), "> ```\n> a `` b\n> ```");
+        assert_eq!(
+            formatter.soft_limit(2).to_string(),
+            "> ```\n> a \n> ```\n> …"
+        );
+
+        // Markdown in the text isn't formatted.
+        let formatter = Blockquote::new("# a\n*b*")
+            .fenced(None)
+            .headings(HeadingPolicy::Strip)
+            .balance_emphasis(true)
+            .soft_limit(6);
+        assert_eq!(formatter.to_string(), "> ```\n> # a\n> *b\n> ```\n> …");
+
+        // The fences count toward the maximum bytes.
+        let formatter = Blockquote::new("abc def ghi").fenced(None).max_bytes(24);
+        assert_eq!(formatter.to_string(), "> ```\n> abc \n> ```\n> …");
+        assert_eq!(Blockquote::new(" \n").fenced(Some("rust")).to_string(), "");
+        assert_eq!(
+            Blockquote::new("a\n")
+                .fenced(None)
+                .keep_trailing_blank_lines(true)
+                .to_string(),
+            "> ```\n> a\n> \n> ```"
+        );
+    }
+
+    #[test]
+    fn test_fenced_language() {
+        const INPUT: &str = "fn main() {\n    println!(\"hi\");\n}";
+
+        let formatter = Blockquote::new(INPUT).fenced(Some("rust"));
+        assert_eq!(
+            formatter.to_string(),
+            "> ```rust\n> fn main() {\n>     println!(\"hi\");\n> }\n> ```"
+        );
+
+        // Only the code counts toward the limits, and the fence is closed
+        // when the code is cut off within a line.
+        assert_eq!(
+            formatter.soft_limit(2).hard_limit(0).to_string(),
+            "> ```rust\n> fn\n> ```\n> …"
+        );
+        assert_eq!(
+            formatter.soft_limit(24).hard_limit(0).to_string(),
+            "> ```rust\n> fn main() {\n>     println!\n> ```\n> …"
+        );
+    }
+
+    #[test]
+    fn test_escape_markdown() {
+        let formatter = Blockquote::new("**not bold** [x](y) # - 1. > a|b ~c~ \\ `d`!");
+        assert_eq!(
+            formatter.escape_markdown(true).to_string(),
+            r"> \*\*not bold\*\* \[x\]\(y\) \# \- 1\. \> a\|b \~c\~ \\ \`d\`\!"
+        );
+
+        let formatter = Blockquote::new("*****").escape_markdown(true);
+        assert_eq!(formatter.to_string(), r"> \*\*\*\*\*");
+
+        // Escapes count toward the limits, but the ellipsis isn't escaped.
+        assert_eq!(formatter.soft_limit(4).to_string(), r"> \*\*…");
+        assert_eq!(
+            Blockquote::new("a *b* c")
+                .escape_markdown(true)
+                .soft_limit(5)
+                .to_string(),
+            r"> a \*b…"
+        );
+        assert_eq!(
+            Blockquote::new("aa **b**")
+                .escape_markdown(true)
+                .wrap(10)
+                .to_string(),
+            "> aa\n> \\*\\*b\\*\\*"
+        );
+    }
+
+    #[test]
+    fn test_escape_telegram() {
+        let formatter =
+            Blockquote::new(r"_*[]()~`>#+-=|{}.!\ a").escape(EscapeProfile::TelegramMarkdownV2);
+        assert_eq!(
+            formatter.to_string(),
+            r"> \_\*\[\]\(\)\~\`\>\#\+\-\=\|\{\}\.\!\\ a"
+        );
+
+        // Only reserved characters are escaped.
+        let formatter = Blockquote::new("> quoted, 50% \"off\"; a@b.c: 1/2 & 3")
+            .escape(EscapeProfile::TelegramMarkdownV2);
+        assert_eq!(
+            formatter.to_string(),
+            r#"> \> quoted, 50% "off"; a@b\.c: 1/2 & 3"#
+        );
+        assert_eq!(
+            formatter.escape(EscapeProfile::Markdown).to_string(),
+            r#"> \> quoted, 50% "off"; a@b\.c: 1/2 & 3"#
+        );
+        assert_eq!(
+            Blockquote::new("a = {b}")
+                .escape(EscapeProfile::Markdown)
+                .to_string(),
+            "> a = {b}"
+        );
+
+        // Escapes count toward the limits, and the ellipsis isn't reserved.
+        let formatter = Blockquote::new("v1.2.3 is out")
+            .escape(EscapeProfile::TelegramMarkdownV2)
+            .soft_limit(8);
+        assert_eq!(formatter.to_string(), r"> v1\.2\.3…");
+    }
+
+    #[test]
+    fn test_backticks() {
+        const INPUT: &str = "a ` b `` c ``` d";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(
+            formatter.backticks(BacktickPolicy::Keep).to_string(),
+            "> a ` b `` c ``` d"
+        );
+        assert_eq!(
+            formatter.backticks(BacktickPolicy::Escape).to_string(),
+            r"> a \` b \`\` c \`\`\` d"
+        );
+        assert_eq!(
+            formatter.backticks(BacktickPolicy::Replace).to_string(),
+            "> a ˋ b ˋˋ c ˋˋˋ d"
+        );
+
+        // Replacements count toward the limits in their own unit.
+        let formatter = formatter
+            .backticks(BacktickPolicy::Replace)
+            .limit_unit(LimitUnit::Bytes);
+        assert_eq!(formatter.soft_limit(6).to_string(), "> a ˋ b…");
+        let formatter = formatter
+            .backticks(BacktickPolicy::Escape)
+            .limit_unit(LimitUnit::Chars);
+        assert_eq!(formatter.soft_limit(6).to_string(), r"> a \` b…");
+
+        // Replacing takes precedence over escaping other punctuation.
+        let formatter = Blockquote::new("`x`")
+            .backticks(BacktickPolicy::Replace)
+            .escape(EscapeProfile::TelegramMarkdownV2);
+        assert_eq!(formatter.to_string(), "> ˋxˋ");
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        const INPUT: &str = "\x1B[1m\x1B[31mbold red\x1B[0m\x1B[0m and \
+            \x1B]8;;https://example.com\x1B\\link\x1B]8;;\x07\n\x1B[0m";
+
+        let formatter = Blockquote::new(INPUT).strip_ansi(true);
+        assert_eq!(formatter.to_string(), "> bold red and link");
+
+        // Incomplete sequences are kept.
+        let formatter = Blockquote::new("a\x1B[31\nb\x1B]8;;c").strip_ansi(true);
+        assert_eq!(formatter.to_string(), "> a\x1B[31\n> b\x1B]8;;c");
+
+        let formatter = Blockquote::new("\x1B[0m \n\x1B[1m").strip_ansi(true);
+        assert!(formatter.is_empty());
+    }
+
+    #[test]
+    fn test_strip_ansi_limit() {
+        const INPUT: &str = "\x1B[32mok\x1B[0m: done \x1B[1;33mwarning\x1B[0m";
+
+        // The limit would land inside the sequence if it were counted.
+        let formatter = Blockquote::new(INPUT).soft_limit(10);
+        assert_eq!(formatter.to_string(), "> \x1B[32mok\x1B[0…");
+
+        let formatter = formatter.strip_ansi(true);
+        assert_eq!(formatter.soft_limit(8).to_string(), "> ok: done…");
+        assert_eq!(formatter.soft_limit(16).to_string(), "> ok: done warning");
+    }
+
+    #[test]
+    fn test_strip_front_matter() {
+        const YAML: &str = "---\ntitle: Notes\ntags: [a, b]\n---\n\nhey, this is cool!";
+        const TOML: &str = "+++\ntitle = \"Notes\"\n+++\nhey, this is cool!";
+
+        let formatter = Blockquote::new(YAML);
+        assert_eq!(
+            formatter.to_string(),
+            "> ---\n> title: Notes\n> tags: [a, b]\n> ---\n> \n> hey, this is cool!"
+        );
+
+        let formatter = formatter.strip_front_matter(true);
+        assert_eq!(formatter.to_string(), "> hey, this is cool!");
+        assert_eq!(formatter.soft_limit(9).to_string(), "> hey, this…");
+        assert_eq!(formatter.emitted_char_count(), 18);
+
+        let formatter =
+            Blockquote::new("---\r\ntitle: Notes\r\n...\r\nhey").strip_front_matter(true);
+        assert_eq!(formatter.to_string(), "> hey");
+
+        let formatter = Blockquote::new(TOML).strip_front_matter(true);
+        assert_eq!(formatter.to_string(), "> hey, this is cool!");
+
+        // Only a blank line after the front matter is skipped.
+        let formatter = Blockquote::new("+++\n+++\n\n\nhey")
+            .strip_front_matter(true)
+            .trim_leading_blank_lines(false);
+        assert_eq!(formatter.to_string(), "> \n> hey");
+
+        let lines = ["---", "title: Notes", "---", "hey", "there"];
+        let formatter = Blockquote::from_lines(&lines).strip_front_matter(true);
+        assert_eq!(formatter.to_string(), "> hey\n> there");
+
+        let formatter = Blockquote::new("---\ntitle: Notes\n---").strip_front_matter(true);
+        assert!(formatter.is_empty());
+        assert_eq!(formatter.to_string(), "");
+    }
+
+    #[test]
+    fn test_strip_front_matter_unterminated() {
+        const INPUT: &str = "---\ntitle: Notes\n\nhey, this is cool!";
+
+        let formatter = Blockquote::new(INPUT).strip_front_matter(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> ---\n> title: Notes\n> \n> hey, this is cool!"
+        );
+
+        // Fences must match, and be the first line.
+        let formatter = Blockquote::new("---\ntitle: Notes\n+++\nhey").strip_front_matter(true);
+        assert_eq!(formatter.to_string(), "> ---\n> title: Notes\n> +++\n> hey");
+
+        let formatter = Blockquote::new("\n---\n---\nhey").strip_front_matter(true);
+        assert_eq!(formatter.to_string(), "> ---\n> ---\n> hey");
+    }
+
+    #[cfg(feature = "strip-markdown")]
+    #[test]
+    fn test_strip_markdown() {
+        const INPUT: &str = "# Release notes ##
+
+Some **bold** and _italic_ text with a [link](https://a.b), <https://c.d>, and `code`.
+
+- first item
+* second ~~item~~
+  1. nested <b>one</b>
+
+```rust
+let x = *y;
+```
+> quoted \\*literal\\* ![logo][l] and ![](e.png)
 
-use core::fmt::{Display, Error as FmtError, Formatter};
+Subtitle
+---
+***
+[l]: https://l.png
+snake_case and 2 * 3";
+        const OUTPUT: &str = "> Release notes
+> \n> Some bold and italic text with a link, https://c.d, and code.
+> \n> first item
+> \n> second item
+> nested one
+> \n> let x = *y;
+> \n> quoted *literal* logo and\x20
+> \n> Subtitle
+> \n> snake_case and 2 * 3";
 
-/// String for starting a blockquote line.
-const BLOCKQUOTE_LINE: &str = "> ";
+        let formatter = Blockquote::new(INPUT).strip_markdown(true);
+        assert_eq!(formatter.to_string(), OUTPUT);
+        assert_eq!(formatter.to_owned().to_string(), OUTPUT);
 
-/// Character for an ellipsis.
-const ELLIPSIS: char = '…';
+        // Limits measure the stripped text.
+        let formatter = Blockquote::new("**bold** [text](https://a.b)").strip_markdown(true);
+        assert_eq!(formatter.soft_limit(7).to_string(), "> bold te…");
+        assert_eq!(formatter.soft_limit(7).emitted_char_count(), 7);
+        assert!(!formatter.soft_limit(9).would_truncate_at(9, None));
 
-/// Character for a newline.
-const NEWLINE: char = '\n';
+        // Prepared blockquotes keep the stripped text.
+        let formatter = formatter.max_bytes(10).ellipsis_replaces(true);
+        let prepared = formatter.prepare();
+        assert_eq!(prepared.stripped.as_deref(), Some("bold text"));
+        assert_eq!(prepared.to_string(), "> bold …");
+        assert_eq!(prepared.to_string(), formatter.to_string());
+        assert_eq!(prepared.rendered_len(), "> bold …".len());
+        assert_eq!(prepared.emitted_char_count(), 5);
+        assert!(prepared.truncated());
 
-/// Quote some text in a markdown blockquote.
-///
-/// # Examples
-///
-/// Quote the text "hey, this is cool!":
-///
-/// ```
-/// use markdown_blockquote_formatter::Blockquote;
-///
-/// let blockquote = Blockquote::new("hey, this is cool!");
-///
-/// assert_eq!(blockquote.to_string(), "> hey, this is cool!");
-/// ```
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub struct Blockquote<'a> {
-    hard_limit: Option<usize>,
-    soft_limit: usize,
-    text: &'a str,
-    with_ellipsis: bool,
-}
+        // Only the text of the markdown is kept, so a lone thematic break is
+        // empty.
+        let formatter = Blockquote::new("---").strip_markdown(true);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.is_empty());
+    }
 
-impl<'a> Blockquote<'a> {
-    /// Create a new markdown blockquote formatter.
-    pub const fn new(text: &'a str) -> Self {
-        Self {
-            hard_limit: None,
-            soft_limit: usize::MAX,
-            text,
-            with_ellipsis: true,
-        }
+    #[cfg(feature = "strip-markdown")]
+    #[test]
+    fn test_strip_markdown_unmatched_delimiters() {
+        let formatter = Blockquote::new("call f(*args)").strip_markdown(true);
+        assert_eq!(formatter.to_string(), "> call f(*args)");
+        let formatter = Blockquote::new("5*3=15").strip_markdown(true);
+        assert_eq!(formatter.to_string(), "> 5*3=15");
+        let formatter = Blockquote::new("~tilde and *half_open").strip_markdown(true);
+        assert_eq!(formatter.to_string(), "> ~tilde and *half_open");
+        let formatter = Blockquote::new("2 * 3 and *em*").strip_markdown(true);
+        assert_eq!(formatter.to_string(), "> 2 * 3 and em");
     }
 
-    /// There is no soft limit in practice by default.
-    pub const fn soft_limit(mut self, soft_limit: usize) -> Self {
-        self.soft_limit = soft_limit;
+    #[test]
+    fn test_prefix_mode() {
+        const INPUT: &str = "quoted text\nlazily continued\n\nafter";
 
-        self
+        let formatter = Blockquote::new(INPUT).prefix_mode(PrefixMode::FirstOnly);
+        let output = formatter.to_string();
+        assert_eq!(output, "> quoted text\nlazily continued\n\nafter");
+        assert_eq!(output.matches("> ").count(), 1);
+
+        // The first line may be the header, and its prefix may be overridden.
+        let formatter = formatter.header("Alice wrote:").indent("  ");
+        assert_eq!(
+            formatter.soft_limit(18).to_string(),
+            "  > Alice wrote:\nquoted text\nlazily…"
+        );
+        assert_eq!(
+            formatter.first_line_prefix("| ").to_string(),
+            "| Alice wrote:\nquoted text\nlazily continued\n\nafter"
+        );
+
+        // Wrapped lines aren't prefixed either, unless continued explicitly.
+        let formatter = Blockquote::new(INPUT)
+            .prefix_mode(PrefixMode::FirstOnly)
+            .wrap(12);
+        assert_eq!(
+            formatter.to_string(),
+            "> quoted text\nlazily\ncontinued\n\nafter"
+        );
+        assert_eq!(
+            formatter.wrap_continuation("> ").to_string(),
+            "> quoted text\nlazily\n> continued\n\nafter"
+        );
+
+        assert_eq!(
+            Blockquote::new(INPUT)
+                .prefix_mode(PrefixMode::EveryLine)
+                .to_string(),
+            Blockquote::new(INPUT).to_string()
+        );
     }
 
-    /// Set the hard limit to break off the formatted text.
-    ///
-    /// The hard limit is *in addition to* the soft limit. Providing a value of
-    /// 50 via [`soft_limit`] and 10 via [`hard_limit`] results in a hard limit
-    /// of 60, saturating at [`usize::MAX`].
-    ///
-    /// There is no hard limit by default.
-    ///
-    /// [`hard_limit`]: Self::hard_limit
-    /// [`soft_limit`]: Self::soft_limit
-    pub const fn hard_limit(mut self, hard_limit: usize) -> Self {
-        self.hard_limit = Some(hard_limit);
+    #[test]
+    fn test_defaults() {
+        assert_eq!(Blockquote::DEFAULT_ELLIPSIS, "…");
+        assert_eq!(Blockquote::DEFAULT_PREFIX, "> ");
 
-        self
+        let formatter = Blockquote::new("hey, this is cool!").soft_limit(9);
+        assert_eq!(
+            formatter.to_string(),
+            format!(
+                "{}hey, this{}",
+                Blockquote::DEFAULT_PREFIX,
+                Blockquote::DEFAULT_ELLIPSIS
+            )
+        );
     }
 
-    /// Whether to include ellipsis upon reaching the end of the formatting.
-    ///
-    /// Ellipsis are included by default.
-    pub const fn with_ellipsis(mut self, with_ellipsis: bool) -> Self {
-        self.with_ellipsis = with_ellipsis;
+    #[test]
+    fn test_alternating_prefixes() {
+        const INPUT: &str = "hi there\nhello\nhow are you?\ngood";
+        const PREFIXES: &[&str] = &["> A: ", "> B: "];
 
-        self
+        let formatter = Blockquote::new(INPUT).alternating_prefixes(PREFIXES);
+        assert_eq!(
+            formatter.to_string(),
+            "> A: hi there\n> B: hello\n> A: how are you?\n> B: good"
+        );
+
+        // The prefixes don't count toward the limits.
+        assert_eq!(
+            formatter.soft_limit(15).to_string(),
+            "> A: hi there\n> B: hello\n> A: …"
+        );
+
+        // Wrapped lines and the header use the usual prefix.
+        let formatter = formatter.wrap(6).header("chat");
+        assert_eq!(
+            formatter.to_string(),
+            "> chat\n> A: hi\n> there\n> B: hello\n> A: how\n> are\n> you?\n> B: good"
+        );
+
+        let formatter = Blockquote::new(INPUT).alternating_prefixes(&["1 ", "2 ", "3 "]);
+        assert_eq!(
+            formatter.to_string(),
+            "1 hi there\n2 hello\n3 how are you?\n1 good"
+        );
     }
 
-    /// Whether the blockquote will be empty upon formatting.
-    ///
-    /// This will be the case if the input text is empty or only consists of
-    /// whitespace.
-    ///
-    /// Blockquotes will short circuit and format nothing when empty.
-    pub fn is_empty(&self) -> bool {
-        self.text.is_empty() || self.text.trim().is_empty()
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_alternating_prefixes_owned() {
+        use super::BlockquoteBuf;
+
+        const PREFIXES: &[&str] = &["> A: ", "> B: "];
+
+        let formatter = Blockquote::new("hi\nhello\nbye").alternating_prefixes(PREFIXES);
+        assert_eq!(formatter.to_owned().to_string(), formatter.to_string());
+        assert_eq!(formatter.to_owned().as_blockquote(), formatter);
+        assert_eq!(
+            formatter.map_lines(str::to_uppercase).to_string(),
+            "> A: HI\n> B: HELLO\n> A: BYE"
+        );
+
+        let formatter = BlockquoteBuf::new("hi\nhello").alternating_prefixes(PREFIXES);
+        assert_eq!(formatter.to_string(), "> A: hi\n> B: hello");
     }
 
-    fn reached_limit(&self, index: usize, soft: bool) -> bool {
-        let limit = if soft {
-            self.soft_limit
-        } else {
-            let hard_limit = self.hard_limit.unwrap_or_default();
+    #[test]
+    fn test_first_line_prefix() {
+        const INPUT: &str = "first line\n\nsecond line";
 
-            self.soft_limit.saturating_add(hard_limit)
-        };
+        let formatter = Blockquote::new(INPUT).first_line_prefix("Alice ❯ ");
+        assert_eq!(
+            formatter.to_string(),
+            "Alice ❯ first line\n> \n> second line"
+        );
 
-        index >= limit
+        // Prefixes don't count toward the limits.
+        let formatter = formatter.soft_limit(18).indent("  ");
+        assert_eq!(
+            formatter.to_string(),
+            "Alice ❯ first line\n  > \n  > second…"
+        );
+
+        let formatter = formatter.header("**Alice:**");
+        assert_eq!(
+            formatter.to_string(),
+            "Alice ❯ **Alice:**\n  > first line\n  > \n  > second…"
+        );
     }
 
-    fn remaining_empty(&self, index: usize) -> bool {
-        self.text
-            .get(index..)
-            .map(|remaining| remaining.trim_end().is_empty())
-            .unwrap_or_default()
+    #[test]
+    fn test_nesting_style() {
+        const INPUT: &str = ">> deepest\n> deeper\nplain";
+
+        let formatter = Blockquote::new(INPUT).nest_existing(true);
+        assert_eq!(formatter.to_string(), "> > > deepest\n> > deeper\n> plain");
+
+        let formatter = formatter.nesting_style(NestingStyle::Contiguous);
+        assert_eq!(formatter.to_string(), ">>> deepest\n>> deeper\n> plain");
+
+        let formatter = formatter.nesting_style(NestingStyle::Spaced);
+        assert_eq!(formatter.to_string(), "> > > deepest\n> > deeper\n> plain");
     }
-}
 
-impl Display for Blockquote<'_> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), FmtError> {
-        #[derive(Clone, Copy, Eq, PartialEq)]
-        enum Stage {
-            Ongoing,
-            StartLine,
-        }
+    #[test]
+    fn test_lazy() {
+        const INPUT: &str = "first paragraph\ncontinues here\n\nsecond paragraph\nends here";
+        const STRICT: &str =
+            "> first paragraph\n> continues here\n> \n> second paragraph\n> ends here";
+        const LAZY: &str = "> first paragraph\ncontinues here\n> \n> second paragraph\nends here";
 
-        if self.is_empty() {
-            return Ok(());
-        }
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), STRICT);
+        assert_eq!(formatter.lazy(true).to_string(), LAZY);
 
-        let chars = self.text.chars();
-        let mut index = 0;
-        let mut stage = Stage::StartLine;
+        // The ellipsis lands on the last line.
+        assert_eq!(
+            formatter.lazy(true).soft_limit(25).to_string(),
+            "> first paragraph\ncontinues…"
+        );
 
-        for character in chars {
-            // Stop if all the remaining text is whitespace.
-            if let Some(text_slice) = self.text.get(index..) {
-                if text_slice.trim_end().is_empty() {
-                    break;
-                }
-            }
+        // Wrapped lines continue the paragraph.
+        let formatter = Blockquote::new("one two three\n- four five")
+            .lazy(true)
+            .wrap(7);
+        assert_eq!(formatter.to_string(), "> one two\nthree\n> - four\n> five");
 
-            if stage == Stage::StartLine {
-                f.write_str(BLOCKQUOTE_LINE)?;
+        // Blocks, fenced code, and changes in depth are prefixed.
+        const BLOCKS: &str = "text\n# heading\n```\ncode\nmore\n```\ntext\n> nested\n> again\nlast";
+        let formatter = Blockquote::new(BLOCKS).lazy(true).nest_existing(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> text\n> # heading\n> ```\n> code\n> more\n> ```\n> text\n> > nested\nagain\n> last"
+        );
+    }
 
-                if character != NEWLINE {
-                    stage = Stage::Ongoing;
-                }
-            }
+    #[test]
+    fn test_render_html() {
+        const INPUT: &str = "first line\nsecond line\n\nthird paragraph & more";
 
-            if self.reached_limit(index, character.is_whitespace()) {
-                break;
-            }
+        let formatter = Blockquote::new(INPUT).soft_limit(40);
+        assert_eq!(
+            formatter.to_string(),
+            "> first line\n> second line\n> \n> third paragraph …"
+        );
+        assert_eq!(
+            formatter.render(Format::Html).to_string(),
+            "<blockquote><p>first line<br>second line</p><p>third paragraph &hellip;</p></blockquote>"
+        );
 
-            write_char(character, f)?;
+        let formatter = formatter.soft_limit(INPUT.len());
+        assert_eq!(
+            formatter.to_string(),
+            "> first line\n> second line\n> \n> third paragraph & more"
+        );
+        assert_eq!(
+            formatter.render(Format::Html).to_string(),
+            "<blockquote><p>first line<br>second line</p><p>third paragraph &amp; more</p></blockquote>"
+        );
+    }
 
-            index += 1;
+    #[test]
+    fn test_render_html_escapes() {
+        let formatter = Blockquote::new("<b>1 > 2</b> & this is a lot more text")
+            .soft_limit(20)
+            .truncation_suffix("<cut>")
+            .render(Format::Html);
+        assert_eq!(
+            formatter.to_string(),
+            "<blockquote><p>&lt;b&gt;1 &gt; 2&lt;/b&gt; &amp; this &lt;cut&gt;</p></blockquote>"
+        );
+    }
 
-            if character == NEWLINE {
-                stage = Stage::StartLine;
-            }
-        }
+    #[test]
+    fn test_render_html_empty() {
+        let formatter = Blockquote::new("").render(Format::Html);
+        assert_eq!(formatter.to_string(), "");
 
-        if self.with_ellipsis && !self.remaining_empty(index) {
-            write_char(ELLIPSIS, f)?;
-        }
+        let formatter = Blockquote::new("\n\ntext\n\n")
+            .line_ending(LineEnding::CrLf)
+            .render(Format::Html);
+        assert_eq!(
+            formatter.to_string(),
+            "<blockquote><p>text</p></blockquote>"
+        );
+    }
 
-        Ok(())
+    #[test]
+    fn test_line_count() {
+        let formatter = Blockquote::new("first\nsecond\n\nthird");
+        assert_eq!(formatter.line_count(), 4);
+        assert_eq!(formatter.trailing_newline(true).line_count(), 4);
+        assert_eq!(formatter.line_ending(LineEnding::CrLf).line_count(), 4);
+        assert_eq!(Blockquote::new("one line").line_count(), 1);
+        assert_eq!(Blockquote::new("").line_count(), 0);
+        assert_eq!(Blockquote::new(" \n ").line_count(), 0);
     }
-}
 
-fn write_char(character: char, f: &mut Formatter<'_>) -> Result<(), FmtError> {
-    let mut buf = [0u8; 4];
-    let string_slice = character.encode_utf8(&mut buf);
+    #[test]
+    fn test_line_count_truncated() {
+        let formatter = Blockquote::new("first\nsecond\nthird").soft_limit(12);
+        assert_eq!(formatter.to_string(), "> first\n> second…");
+        assert_eq!(formatter.line_count(), 2);
 
-    f.write_str(string_slice)
-}
+        let formatter = formatter.ellipsis_on_new_line(true);
+        assert_eq!(formatter.to_string(), "> first\n> second\n> …");
+        assert_eq!(formatter.line_count(), 3);
+        assert_eq!(formatter.trailing_newline(true).line_count(), 3);
 
-#[cfg(test)]
-mod tests {
-    use super::Blockquote;
-    use alloc::{borrow::ToOwned, fmt::Debug, string::ToString};
-    use static_assertions::assert_impl_all;
+        let formatter = Blockquote::new("first second third").soft_limit(5);
+        assert_eq!(formatter.line_count(), 1);
+        assert_eq!(formatter.ellipsis_on_new_line(true).line_count(), 2);
+    }
+
+    #[test]
+    fn test_whitespace_chars() {
+        let formatter = Blockquote::new("\u{2800}\u{2800}\n \u{2800}");
+        assert!(!formatter.is_empty());
+        assert_eq!(formatter.to_string(), "> \u{2800}\u{2800}\n>  \u{2800}");
+
+        let formatter = formatter.whitespace_chars("\u{2800}");
+        assert!(formatter.is_empty());
+        assert_eq!(formatter.to_string(), "");
+        assert!(!formatter.whitespace_chars("-").is_empty());
 
-    assert_impl_all!(Blockquote: Debug, Send, Sync);
+        // No-break spaces are already whitespace.
+        let formatter = Blockquote::new("\u{A0}\n\u{A0}\u{2800}").whitespace_chars("\u{2800}");
+
+        assert!(formatter.is_empty());
+        assert!(!formatter.whitespace_chars("").is_empty());
+    }
 
     #[test]
-    fn test_simple() {
-        const INPUT: &str = "this is a simple test";
-        const OUTPUT: &str = "> this is a simple test";
+    fn test_whitespace_chars_blank_lines() {
+        let formatter =
+            Blockquote::new("--\ntext\u{2800}-\n\u{2800}").whitespace_chars("\u{2800}-");
+        assert!(!formatter.is_empty());
+        assert_eq!(formatter.to_string(), "> text");
+        assert_eq!(
+            Blockquote::new("a\u{2800}b")
+                .whitespace_chars("\u{2800}")
+                .to_string(),
+            "> a\u{2800}b"
+        );
+    }
 
-        let formatter = Blockquote::new(INPUT).soft_limit(50);
+    #[test]
+    fn test_render_rst() {
+        let formatter = Blockquote::new("first line\nsecond line\n\n  indented")
+            .line_ending(LineEnding::CrLf)
+            .render(Format::Rst);
+        assert_eq!(
+            formatter.to_string(),
+            "    first line\r\n    second line\r\n\r\n      indented"
+        );
+        assert_eq!(
+            formatter.rst_indent(1).trailing_newline(true).to_string(),
+            " first line\r\n second line\r\n\r\n   indented\r\n"
+        );
+        assert_eq!(Blockquote::new(" \n").render(Format::Rst).to_string(), "");
+    }
 
-        assert_eq!(formatter.to_string(), OUTPUT);
+    #[test]
+    fn test_render_rst_truncated() {
+        let formatter = Blockquote::new("first line\n\nsecond line").soft_limit(17);
+        assert_eq!(formatter.to_string(), "> first line\n> \n> secon…");
+        assert_eq!(
+            formatter.render(Format::Rst).to_string(),
+            "    first line\n\n    secon…"
+        );
+        assert_eq!(
+            formatter
+                .render(Format::Rst)
+                .ellipsis_on_new_line(true)
+                .truncation_suffix("[...]")
+                .to_string(),
+            "    first line\n\n    secon\n    [...]"
+        );
+
+        let formatter = Blockquote::new("hey, this is cool!").render(Format::Rst);
+        assert_eq!(formatter.max_bytes(16).to_string(), "    hey, this…");
+        assert_eq!(formatter.max_bytes(16).prepare().rendered_len(), 16);
     }
 
     #[test]
-    fn test_is_empty() {
-        assert!(Blockquote::new("").is_empty());
-        assert!(Blockquote::new(" \n  \t ").is_empty());
+    fn test_quote_each() {
+        const INPUT: &str = "first line\nsecond line\n\n\nthird line\n";
+
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(
+            formatter.quote_each().to_string(),
+            "> first line\n> second line\n\n> third line"
+        );
+        assert_eq!(
+            formatter.trailing_newline(true).quote_each().to_string(),
+            "> first line\n> second line\n\n> third line\n"
+        );
+
+        let lines = ["first line", "second line", " ", "third line"];
+        assert_eq!(
+            Blockquote::from_lines(&lines).quote_each().to_string(),
+            "> first line\n> second line\n\n> third line"
+        );
+        assert_eq!(Blockquote::new("\n \n").quote_each().to_string(), "");
     }
 
     #[test]
-    fn test_newlines() {
-        const EXPECTED: &str = "> test\n> two\n> three";
+    fn test_quote_each_limit() {
+        const INPUT: &str = "first line\n\nsecond line\n\nthird line";
 
-        let formatter = Blockquote::new("test\ntwo\nthree").soft_limit(50);
+        let formatter = Blockquote::new(INPUT).soft_limit(16);
+        assert_eq!(
+            formatter.quote_each().to_string(),
+            "> first line\n\n> second…"
+        );
+        assert_eq!(
+            formatter.soft_limit(21).quote_each().to_string(),
+            "> first line\n\n> second line\n\n> …"
+        );
+        assert_eq!(
+            formatter.soft_limit(31).quote_each().to_string(),
+            "> first line\n\n> second line\n\n> third line"
+        );
 
-        assert_eq!(formatter.to_string(), EXPECTED);
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(
+            formatter.max_bytes(30).quote_each().to_string(),
+            "> first line\n\n> second line"
+        );
+        assert_eq!(
+            formatter.max_bytes(25).quote_each().to_string(),
+            "> first line\n\n> second…"
+        );
+
+        for max_bytes in 0..=50 {
+            let formatted = formatter.max_bytes(max_bytes).quote_each().to_string();
+            assert!(formatted.len() <= max_bytes);
+        }
     }
 
     #[test]
-    fn test_soft_limit() {
-        const EXPECTED: &str = "> this is just:\n> a really cool…";
+    fn test_render_asciidoc() {
+        let formatter = Blockquote::new("first line\n\nsecond line").render(Format::AsciiDoc);
+        assert_eq!(
+            formatter.to_string(),
+            "[quote]\n____\nfirst line\n\nsecond line\n____"
+        );
+        assert_eq!(
+            formatter.attribution("Jane Doe").to_string(),
+            "[quote, Jane Doe]\n____\nfirst line\n\nsecond line\n____"
+        );
+        assert_eq!(
+            formatter
+                .attribution("Doe, \"Jane\"")
+                .line_ending(LineEnding::CrLf)
+                .trailing_newline(true)
+                .to_string(),
+            "[quote, \"Doe, \\\"Jane\\\"\"]\r\n____\r\nfirst line\r\n\r\nsecond line\r\n____\r\n"
+        );
+        assert_eq!(
+            Blockquote::new(" \n")
+                .attribution("Jane Doe")
+                .render(Format::AsciiDoc)
+                .to_string(),
+            ""
+        );
+    }
 
-        let before_cut = "this is just:\na really co";
-        let after_cut = "ol test!";
-        let text = before_cut.to_owned() + after_cut;
+    #[test]
+    fn test_render_asciidoc_truncated() {
+        let formatter = Blockquote::new("first line\nsecond line")
+            .render(Format::AsciiDoc)
+            .soft_limit(17);
+        assert_eq!(
+            formatter.to_string(),
+            "[quote]\n____\nfirst line\nsecond…\n____"
+        );
 
-        let formatter = Blockquote::new(&text)
-            .soft_limit(before_cut.len())
-            .hard_limit(10);
+        let formatter = formatter.soft_limit(usize::MAX).max_bytes(35);
+        let formatted = formatter.to_string();
+        assert_eq!(formatted, "[quote]\n____\nfirst line\nsec…\n____");
+        assert_eq!(formatted.len(), 35);
+        assert_eq!(formatter.max_bytes(18).to_string(), "");
+    }
 
-        assert_eq!(formatter.to_string(), EXPECTED);
+    #[test]
+    fn test_render_asciidoc_escapes() {
+        let formatter = Blockquote::new("a\n____\nb\n  _____ c\n___").render(Format::AsciiDoc);
+        assert_eq!(
+            formatter.to_string(),
+            "[quote]\n____\na\n{empty}____\nb\n  {empty}_____ c\n___\n____"
+        );
+
+        let formatter = Blockquote::new("a\n____\nb").render(Format::AsciiDoc);
+        let formatted = formatter.max_bytes(32).to_string();
+        assert_eq!(formatted, "[quote]\n____\na\n___…\n____");
+        assert!(formatted.len() <= 32);
+        assert_eq!(
+            formatter.max_bytes(33).to_string(),
+            "[quote]\n____\na\n{empty}____\nb\n____"
+        );
     }
 
     #[test]
-    fn test_soft_limit_cutoff() {
-        const EXPECTED: &str = "> this is just:\n> a really coo…";
+    fn test_normalize_alert_case() {
+        const INPUT: &str = "\n[!Note]  \nback up\n[!tip]";
 
-        let before_cut = "this is just:\na really co";
-        let after_cut = "ol test!";
-        let text = before_cut.to_owned() + after_cut;
+        let formatter = Blockquote::new(INPUT);
+        assert_eq!(formatter.to_string(), "> [!Note]  \n> back up\n> [!tip]");
 
-        let formatter = Blockquote::new(&text)
-            .soft_limit(before_cut.len())
-            .hard_limit(1);
+        // Only the marker on the first line is an alert marker.
+        let formatter = formatter.normalize_alert_case(true);
+        assert_eq!(formatter.to_string(), "> [!NOTE]  \n> back up\n> [!tip]");
+        assert_eq!(formatter.soft_limit(5).to_string(), "> [!NOT…");
 
-        assert_eq!(formatter.to_string(), EXPECTED);
+        let formatter = Blockquote::new("[!note] back up").normalize_alert_case(true);
+        assert_eq!(formatter.to_string(), "> [!note] back up");
+
+        let formatter = Blockquote::new("back up")
+            .alert(AlertKind::Custom("Danger"))
+            .normalize_alert_case(true);
+        assert_eq!(formatter.to_string(), "> [!DANGER]\n> back up");
+        assert_eq!(
+            formatter.normalize_alert_case(false).to_string(),
+            "> [!Danger]\n> back up"
+        );
     }
 
     #[test]
-    fn test_soft_limit_none() {
-        const INPUT: &str = "this text is too long :(";
-        const OUTPUT: &str = "> this text is too lo…";
+    fn test_render_org() {
+        let formatter = Blockquote::new("first line\n\nsecond line").render(Format::Org);
+        assert_eq!(
+            formatter.to_string(),
+            "#+BEGIN_QUOTE\nfirst line\n\nsecond line\n#+END_QUOTE"
+        );
+        assert_eq!(
+            formatter
+                .leading_newline(true)
+                .trailing_newline(true)
+                .to_string(),
+            "\n#+BEGIN_QUOTE\nfirst line\n\nsecond line\n#+END_QUOTE\n"
+        );
+        assert_eq!(Blockquote::new(" \n").render(Format::Org).to_string(), "");
+    }
 
-        let formatter = Blockquote::new(INPUT).soft_limit(19);
+    #[test]
+    fn test_render_org_escapes() {
+        let formatter =
+            Blockquote::new("a\n#+END_QUOTE\nb\n  #+end_quote c\n#+END").render(Format::Org);
+        assert_eq!(
+            formatter.to_string(),
+            "#+BEGIN_QUOTE\na\n,#+END_QUOTE\nb\n  ,#+end_quote c\n#+END\n#+END_QUOTE"
+        );
 
-        assert_eq!(formatter.to_string(), OUTPUT);
+        let formatter = Blockquote::new("a\n#+END_QUOTE\nb").render(Format::Org);
+        let formatted = formatter.max_bytes(41).to_string();
+        assert_eq!(formatted, "#+BEGIN_QUOTE\na\n#+END_QUOT…\n#+END_QUOTE");
+        assert!(formatted.len() <= 41);
+        assert_eq!(
+            formatter.max_bytes(42).to_string(),
+            "#+BEGIN_QUOTE\na\n,#+END_QUOTE\nb\n#+END_QUOTE"
+        );
     }
 
     #[test]
-    fn test_soft_limit_zero() {
-        const INPUT: &str = "this text is too long :(";
-        const OUTPUT: &str = "> this text is too lo…";
+    fn test_render_org_truncated() {
+        let formatter = Blockquote::new("first line\nsecond line").render(Format::Org);
+        assert_eq!(
+            formatter.soft_limit(17).to_string(),
+            "#+BEGIN_QUOTE\nfirst line\nsecond…\n#+END_QUOTE"
+        );
 
-        let formatter = Blockquote::new(INPUT).soft_limit(19);
+        let formatted = formatter.max_bytes(45).to_string();
+        assert_eq!(formatted, "#+BEGIN_QUOTE\nfirst line\nsecon…\n#+END_QUOTE");
+        assert_eq!(formatted.len(), 45);
+        assert_eq!(formatter.max_bytes(27).to_string(), "");
+    }
 
-        assert_eq!(formatter.to_string(), OUTPUT);
+    #[test]
+    fn test_render_plain() {
+        const INPUT: &str = "first *line*\n\nsecond line\nthird line";
+
+        let formatter = Blockquote::new(INPUT).soft_limit(31);
+        assert_eq!(
+            formatter.to_string(),
+            "> first *line*\n> \n> second line\n> third…"
+        );
+        assert_eq!(
+            formatter.render(Format::Plain).to_string(),
+            "    first *line*\n\n    second line\n    third…"
+        );
+        assert_eq!(
+            formatter
+                .render(Format::Plain)
+                .plain_indent("\t")
+                .ellipsis_on_new_line(true)
+                .to_string(),
+            "\tfirst *line*\n\n\tsecond line\n\tthird\n\t…"
+        );
+        assert_eq!(
+            formatter.render(Format::Plain).plain_indent("").to_string(),
+            "first *line*\n\nsecond line\nthird…"
+        );
+
+        // Text cut off before anything is formatted is empty without an
+        // ellipsis.
+        let formatter = Blockquote::new(INPUT)
+            .render(Format::Plain)
+            .soft_limit(0)
+            .with_ellipsis(false);
+        assert_eq!(formatter.to_string(), "");
+        assert!(formatter.is_empty());
+        assert_eq!(formatter.with_ellipsis(true).to_string(), "    …");
+        assert!(!formatter.with_ellipsis(true).is_empty());
     }
 
     #[test]
-    fn test_end_with_newline() {
-        const INPUT: &str = "test\nagain\n\n";
-        const OUTPUT: &str = "> test\n> again";
+    fn test_cut_byte_offset() {
+        const INPUT: &str = "first líne\nsecond line";
 
         let formatter = Blockquote::new(INPUT);
-        assert_eq!(formatter.to_string(), OUTPUT);
+        assert_eq!(formatter.cut_byte_offset(), None);
+
+        for soft_limit in 0..INPUT.chars().count() {
+            let formatter = formatter.soft_limit(soft_limit);
+            let offset = formatter.cut_byte_offset().unwrap();
+            let shown = formatter.emitted_char_count();
+            assert!(INPUT.is_char_boundary(offset));
+            assert_eq!(INPUT[..offset].chars().count(), shown);
+        }
+
+        let formatter = formatter.soft_limit(8);
+        assert_eq!(formatter.to_string(), "> first lí…");
+        assert_eq!(
+            &INPUT[formatter.cut_byte_offset().unwrap()..],
+            "ne\nsecond line"
+        );
+
+        let lines = ["first", "second line"];
+        let formatter = Blockquote::from_lines(&lines).soft_limit(9);
+        assert_eq!(formatter.cut_byte_offset(), Some(9));
+
+        const FRONT_MATTER: &str = "---\ntitle: x\n---\nhey, this is cool!";
+
+        let formatter = Blockquote::new(FRONT_MATTER)
+            .strip_front_matter(true)
+            .soft_limit(4);
+        let offset = formatter.cut_byte_offset().unwrap();
+        assert_eq!(&FRONT_MATTER[offset..], " this is cool!");
+    }
+
+    #[cfg(feature = "strip-markdown")]
+    #[test]
+    fn test_cut_byte_offset_strip_markdown() {
+        let formatter = Blockquote::new("---\nt: x\n---\n# Hi *there*")
+            .strip_front_matter(true)
+            .strip_markdown(true)
+            .soft_limit(3);
+        assert_eq!(formatter.to_string(), "> Hi …");
+        assert_eq!(formatter.cut_byte_offset(), None);
+    }
+
+    #[test]
+    fn test_render_discord_multiline() {
+        let formatter = Blockquote::new("first line\n\nsecond line")
+            .attribution("Jane Doe")
+            .render(Format::DiscordMultiline);
+        assert_eq!(formatter.to_string(), ">>> first line\n\nsecond line");
+        assert_eq!(
+            formatter.leading_newline(true).to_string(),
+            "\n>>> first line\n\nsecond line"
+        );
+        assert_eq!(
+            Blockquote::new(" \n")
+                .render(Format::DiscordMultiline)
+                .to_string(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_render_discord_multiline_truncated() {
+        let formatter = Blockquote::new("first line\nsecond line").render(Format::DiscordMultiline);
+        assert_eq!(
+            formatter.soft_limit(17).to_string(),
+            ">>> first line\nsecond…"
+        );
+
+        let formatted = formatter.max_bytes(24).to_string();
+        assert_eq!(formatted, ">>> first line\nsecond…");
+        assert_eq!(formatted.len(), 24);
+        assert_eq!(formatter.max_bytes(6).to_string(), "");
+
+        // The marker is counted once, rather than for every line.
+        let formatter = Blockquote::new("one\ntwo\nthree").max_bytes(17);
+
+        assert_eq!(
+            formatter.render(Format::DiscordMultiline).to_string(),
+            ">>> one\ntwo\nthree"
+        );
+        assert_eq!(formatter.to_string(), "> one\n> two\n> …");
+    }
+
+    #[test]
+    fn test_html_line_breaks() {
+        const INPUT: &str = "first line\nsecond line\n\n```\ncode\nmore\n```\n| a |\n| - |\n\nlast";
+
+        let formatter = Blockquote::new(INPUT).html_line_breaks(true);
+        assert_eq!(
+            formatter.to_string(),
+            "> first line<br>\n> second line\n> \n> ```\n> code\n> more\n> ```\n> | a |\n> | - |\n> \n> last"
+        );
+
+        let formatter = Blockquote::new("first line\nsecond line\n")
+            .html_line_breaks(true)
+            .line_ending(LineEnding::CrLf);
+        assert_eq!(formatter.to_string(), "> first line<br>\r\n> second line");
+        assert_eq!(
+            formatter.soft_limit(12).to_string(),
+            "> first line<br>\r\n> s…"
+        );
+
+        let formatter = Blockquote::new("first\nsecond")
+            .html_line_breaks(true)
+            .strikethrough(true);
+        assert_eq!(formatter.to_string(), "> ~~first~~<br>\n> ~~second~~");
+    }
+
+    #[test]
+    fn test_filter_lines_starting_with() {
+        const INPUT: &str = "DEBUG connecting\nERROR timed out\nINFO retrying\n\nDEBUG waiting\nERROR no route\nERROR giving up\nINFO done";
+
+        let formatter = Blockquote::new(INPUT).filter_lines_starting_with("ERROR");
+        assert_eq!(
+            formatter.to_string(),
+            "> ERROR timed out\n> ERROR no route\n> ERROR giving up"
+        );
+
+        // Only the quoted lines count toward the limits.
+        assert_eq!(
+            formatter.soft_limit(27).to_string(),
+            "> ERROR timed out\n> ERROR no ro…"
+        );
+        assert_eq!(
+            formatter.line_numbers(1).to_string(),
+            "> 2 | ERROR timed out\n> 6 | ERROR no route\n> 7 | ERROR giving up"
+        );
+        assert_eq!(
+            formatter.filter_lines_starting_with("INFO").to_string(),
+            "> INFO retrying\n> INFO done"
+        );
+        assert_eq!(
+            formatter.filter_lines_starting_with("").to_string(),
+            Blockquote::new(INPUT).to_string()
+        );
+
+        let formatter = formatter.filter_lines_starting_with("WARN");
+        assert!(formatter.is_empty());
+        assert_eq!(formatter.to_string(), "");
+    }
+
+    #[test]
+    fn test_render_jira() {
+        let formatter = Blockquote::new("first line\n\nsecond line").render(Format::Jira);
+        assert_eq!(
+            formatter.to_string(),
+            "{quote}\nfirst line\n\nsecond line\n{quote}"
+        );
+        assert_eq!(
+            formatter.soft_limit(18).to_string(),
+            "{quote}\nfirst line\n\nsecond…\n{quote}"
+        );
+        assert_eq!(
+            formatter
+                .truncation_suffix("{...}")
+                .soft_limit(18)
+                .to_string(),
+            "{quote}\nfirst line\n\nsecond\\{...\\}\n{quote}"
+        );
+        assert_eq!(Blockquote::new(" \n").render(Format::Jira).to_string(), "");
+    }
+
+    #[test]
+    fn test_render_jira_escapes() {
+        let formatter =
+            Blockquote::new("before\n{quote}\nafter {QUOTE} {color:red}").render(Format::Jira);
+        assert_eq!(
+            formatter.to_string(),
+            "{quote}\nbefore\n\\{quote\\}\nafter \\{QUOTE\\} \\{color:red\\}\n{quote}"
+        );
+
+        let formatted = formatter.max_bytes(30).to_string();
+        assert_eq!(formatted, "{quote}\nbefore\n\\{qu…\n{quote}");
+        assert!(formatted.len() <= 30);
+    }
+
+    #[test]
+    fn test_ellipsis_line_marker() {
+        const INPUT: &str = "first line\nsecond line\nthird line";
+
+        let formatter = Blockquote::new(INPUT)
+            .soft_limit(17)
+            .ellipsis_line_marker("[more]");
+        assert_eq!(formatter.to_string(), "> first line\n> second…");
+
+        let formatter = formatter.ellipsis_on_new_line(true);
+        assert_eq!(formatter.to_string(), "> first line\n> second\n> [more]");
+        assert_eq!(
+            formatter.soft_limit(22).to_string(),
+            "> first line\n> second line\n> [more]"
+        );
+        assert_eq!(
+            formatter.truncation_suffix("...").to_string(),
+            "> first line\n> second\n> [more]"
+        );
+
+        // Lines cut off by the per-line limit keep the truncation suffix.
+        assert_eq!(
+            formatter.soft_limit(30).per_line_limit(5).to_string(),
+            "> first…\n> secon…\n> third…"
+        );
+    }
+
+    #[test]
+    fn test_render_textile() {
+        let formatter = Blockquote::new("first line\nsecond line").render(Format::Textile);
+        assert_eq!(formatter.to_string(), "bq. first line\nsecond line");
+        assert_eq!(
+            formatter.soft_limit(17).to_string(),
+            "bq. first line\nsecond…"
+        );
+        assert_eq!(
+            formatter.trailing_newline(true).to_string(),
+            "bq. first line\nsecond line\n"
+        );
+        assert_eq!(
+            Blockquote::new(" \n").render(Format::Textile).to_string(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_render_textile_extended() {
+        const INPUT: &str = "first line\n\nsecond line\n\nthird line";
+
+        let formatter = Blockquote::new(INPUT).render(Format::Textile);
+        assert_eq!(
+            formatter.to_string(),
+            "bq.. first line\n\nsecond line\n\nthird line\n\np."
+        );
+        assert_eq!(
+            formatter.line_ending(LineEnding::CrLf).to_string(),
+            "bq.. first line\r\n\r\nsecond line\r\n\r\nthird line\r\n\r\np."
+        );
+        assert_eq!(
+            formatter.soft_limit(18).to_string(),
+            "bq.. first line\n\nsecond…\n\np."
+        );
+
+        // Quotes cut off in the first paragraph aren't extended.
+        assert_eq!(formatter.soft_limit(5).to_string(), "bq. first…");
+
+        let formatted = formatter.max_bytes(29).to_string();
+        assert_eq!(formatted, "bq.. first line\n\nsecon…\n\np.");
+        assert!(formatted.len() <= 29);
     }
 }
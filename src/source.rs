@@ -0,0 +1,329 @@
+//! Text to be quoted, and iterators over its lines and characters.
+
+use super::{CARRIAGE_RETURN, NEWLINE};
+use core::slice::Iter;
+
+/// Character for the Unicode line separator.
+const LINE_SEPARATOR: char = '\u{2028}';
+
+/// Character for the next line control.
+const NEXT_LINE: char = '\u{85}';
+
+/// Character for the Unicode paragraph separator.
+const PARAGRAPH_SEPARATOR: char = '\u{2029}';
+
+/// Text to be quoted.
+///
+/// Positions in a source are byte positions in its text as if all of its lines
+/// were joined by newlines.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum Source<'a> {
+    /// Text already split into lines.
+    Lines(&'a [&'a str]),
+    /// Text with lines separated by line breaks.
+    Text(&'a str),
+}
+
+impl<'a> Source<'a> {
+    /// Number of characters of the source before a position.
+    pub(crate) fn char_count(self, position: usize) -> usize {
+        match self {
+            Self::Lines(lines) => {
+                let mut count = 0;
+                let mut start = 0;
+
+                for (index, line) in lines.iter().enumerate() {
+                    if position <= start {
+                        break;
+                    }
+
+                    let end = start + line.len();
+
+                    count += line[..position.min(end) - start].chars().count();
+
+                    // Lines are joined by newlines.
+                    if position > end && index + 1 < lines.len() {
+                        count += 1;
+                    }
+
+                    start = end + 1;
+                }
+
+                count
+            }
+            Self::Text(text) => text[..position.min(text.len())].chars().count(),
+        }
+    }
+
+    /// Length of the source in bytes.
+    pub(crate) fn len(self) -> usize {
+        match self {
+            Self::Lines(lines) => {
+                let breaks = lines.len().saturating_sub(1);
+
+                lines.iter().map(|line| line.len()).sum::<usize>() + breaks
+            }
+            Self::Text(text) => text.len(),
+        }
+    }
+
+    /// Source after the front matter starting it, if any.
+    ///
+    /// Front matter is fenced by lines of `---`, and can be closed by `...`
+    /// instead, or by lines of `+++`. A blank line following it is skipped
+    /// too. Front matter that isn't closed is kept, as it's likely content.
+    pub(crate) fn after_front_matter(self, unicode: bool) -> Self {
+        let mut lines = self.lines(unicode);
+
+        let closing: &[&str] = match lines.next().map(|line| line.text) {
+            Some("---") => &["---", "..."],
+            Some("+++") => &["+++"],
+            _ => return self,
+        };
+
+        if !lines.any(|line| closing.contains(&line.text)) {
+            return self;
+        }
+
+        let start = match lines.next() {
+            Some(line) if line.text.trim().is_empty() => lines.next(),
+            line => line,
+        }
+        .map_or(usize::MAX, |line| line.position);
+
+        match self {
+            Self::Lines(lines) => {
+                let mut line_start = 0;
+
+                for (index, line) in lines.iter().enumerate() {
+                    if start == line_start {
+                        return Self::Lines(&lines[index..]);
+                    }
+
+                    // Only the last of the lines can be split, as the rest
+                    // of a line can't be borrowed along with the lines after
+                    // it.
+                    if start < line_start + line.len() {
+                        return match lines.len() - index {
+                            1 => Self::Text(&line[start - line_start..]),
+                            _ => self,
+                        };
+                    }
+
+                    line_start += line.len() + 1;
+                }
+
+                Self::Lines(&[])
+            }
+            Self::Text(text) => Self::Text(text.get(start..).unwrap_or_default()),
+        }
+    }
+
+    /// Part of the source between two positions.
+    ///
+    /// Lines can't be split and joined again, so the part of lines is
+    /// widened to whole lines unless it's within a single line.
+    pub(crate) fn slice(self, start: usize, end: usize) -> Self {
+        match self {
+            Self::Lines(lines) => {
+                let mut first = None;
+                let mut line_start = 0;
+
+                for (index, line) in lines.iter().enumerate() {
+                    let line_end = line_start + line.len();
+
+                    if first.is_none() && start <= line_end {
+                        first = Some(index);
+                    }
+
+                    if end <= line_end {
+                        return match first {
+                            Some(first) if first == index => {
+                                Self::Text(&line[start - line_start..end - line_start])
+                            }
+                            Some(first) => Self::Lines(&lines[first..=index]),
+                            None => Self::Lines(&[]),
+                        };
+                    }
+
+                    line_start = line_end + 1;
+                }
+
+                Self::Lines(&lines[first.unwrap_or(lines.len())..])
+            }
+            Self::Text(text) => Self::Text(&text[start.min(text.len())..end.min(text.len())]),
+        }
+    }
+
+    /// Iterator over the characters of the source.
+    ///
+    /// Whether carriage returns and the Unicode line and paragraph separators
+    /// are line breaks is determined by `unicode`.
+    pub(crate) fn chars(self, unicode: bool) -> Chars<'a> {
+        let mut lines = self.lines(unicode);
+        let line = lines.next().unwrap_or_default();
+
+        Chars {
+            byte: 0,
+            line,
+            lines,
+        }
+    }
+
+    /// Iterator over the lines of the source.
+    ///
+    /// Whether carriage returns and the Unicode line and paragraph separators
+    /// are line breaks is determined by `unicode`.
+    pub(crate) fn lines(self, unicode: bool) -> Lines<'a> {
+        let (rest, remaining) = match self {
+            Self::Lines(lines) => {
+                let mut remaining = lines.iter();
+
+                (remaining.next().copied(), remaining)
+            }
+            Self::Text(text) => (Some(text), [].iter()),
+        };
+
+        Lines {
+            blank: None,
+            position: 0,
+            remaining,
+            rest,
+            unicode,
+        }
+    }
+}
+
+/// Line of a source.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) struct Line<'a> {
+    /// Position of the start of the line.
+    pub(crate) position: usize,
+    /// Text of the line, without its line break.
+    pub(crate) text: &'a str,
+}
+
+impl Line<'_> {
+    /// Position of the end of the line, which is the start of its line break.
+    pub(crate) const fn end(&self) -> usize {
+        self.position + self.text.len()
+    }
+}
+
+/// Iterator over the characters of a source.
+///
+/// Yields the position of each character, the character, and the remainder of
+/// its line starting with the character. Line breaks are yielded as newlines
+/// with the entirety of the following line.
+#[derive(Clone, Debug)]
+pub(crate) struct Chars<'a> {
+    /// Byte position within the current line.
+    byte: usize,
+    line: Line<'a>,
+    lines: Lines<'a>,
+}
+
+impl<'a> Chars<'a> {
+    /// Text of the line following the current line, if any.
+    pub(crate) fn next_line(&self) -> Option<&'a str> {
+        self.lines.clone().next().map(|line| line.text)
+    }
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = (usize, char, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.line.text[self.byte..];
+
+        if let Some(character) = rest.chars().next() {
+            let position = self.line.position + self.byte;
+            self.byte += character.len_utf8();
+
+            return Some((position, character, rest));
+        }
+
+        let line = self.lines.next()?;
+        let position = self.line.end();
+
+        self.byte = 0;
+        self.line = line;
+
+        Some((position, NEWLINE, line.text))
+    }
+}
+
+/// Iterator over the lines of a source.
+///
+/// Lines are separated by newlines, optionally preceded by a carriage return.
+/// Lines of a source already split into lines are further split at any line
+/// breaks they contain.
+///
+/// Paragraph separators are followed by an additional blank line, positioned
+/// at the start of the paragraph separator.
+#[derive(Clone, Debug)]
+pub(crate) struct Lines<'a> {
+    /// Position of a blank line to yield next.
+    blank: Option<usize>,
+    /// Position of the start of the next line.
+    position: usize,
+    remaining: Iter<'a, &'a str>,
+    /// Remaining text of the current line of the source.
+    rest: Option<&'a str>,
+    unicode: bool,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(position) = self.blank.take() {
+            return Some(Line { position, text: "" });
+        }
+
+        let rest = self.rest?;
+        let position = self.position;
+
+        // A carriage return at the end of a line of a source already split
+        // into lines is part of the line break between the lines.
+        let text = if self.remaining.as_slice().is_empty() {
+            rest
+        } else {
+            rest.strip_suffix(CARRIAGE_RETURN).unwrap_or(rest)
+        };
+
+        if let Some((index, len, paragraph)) = find_line_break(text, self.unicode) {
+            if paragraph {
+                self.blank = Some(position + index);
+            }
+
+            self.position += index + len;
+            self.rest = Some(&rest[index + len..]);
+
+            return Some(Line {
+                position,
+                text: &text[..index],
+            });
+        }
+
+        self.position += rest.len() + 1;
+        self.rest = self.remaining.next().copied();
+
+        Some(Line { position, text })
+    }
+}
+
+/// Find the first line break in text, returning its byte position, its length,
+/// and whether it's a paragraph separator.
+fn find_line_break(text: &str, unicode: bool) -> Option<(usize, usize, bool)> {
+    text.char_indices()
+        .find_map(|(index, character)| match character {
+            NEWLINE => Some((index, 1, false)),
+            CARRIAGE_RETURN if text[index + 1..].starts_with(NEWLINE) => Some((index, 2, false)),
+            CARRIAGE_RETURN | LINE_SEPARATOR | NEXT_LINE if unicode => {
+                Some((index, character.len_utf8(), false))
+            }
+            PARAGRAPH_SEPARATOR if unicode => Some((index, character.len_utf8(), true)),
+            _ => None,
+        })
+}
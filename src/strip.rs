@@ -0,0 +1,93 @@
+//! Conversion of markdown to plain text, for [`Blockquote::strip_markdown`].
+//!
+//! [`Blockquote::strip_markdown`]: super::Blockquote::strip_markdown
+
+use super::{Source, NEWLINE};
+use alloc::string::String;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// Separator written between two lines of plain text.
+const LINE_BREAK: &str = "\n";
+
+/// Separator written between two blocks of plain text.
+const BLANK_LINE: &str = "\n\n";
+
+/// Plain text of markdown, without the syntax of blocks and inline formatting.
+///
+/// The markdown is parsed by `pulldown-cmark`, and only its text is kept.
+/// Blocks are separated by a blank line, and the items of tight lists,
+/// including nested lists, by a line break. Lines of code blocks are kept
+/// verbatim, without their fences, code spans are unwrapped, and links and
+/// images are replaced by their label and alt text. HTML, thematic breaks, and
+/// link reference definitions are removed.
+pub(crate) fn strip_markdown(source: Source<'_>, unicode_newlines: bool) -> String {
+    let mut markdown = String::with_capacity(source.len());
+
+    for (index, line) in source.lines(unicode_newlines).enumerate() {
+        if index > 0 {
+            markdown.push(NEWLINE);
+        }
+
+        markdown.push_str(line.text);
+    }
+
+    let mut text = String::new();
+    let mut separator = "";
+    // Depth of the list items the events are in.
+    let mut items = 0_usize;
+
+    for event in Parser::new_ext(&markdown, Options::ENABLE_STRIKETHROUGH) {
+        let content = match event {
+            Event::Start(Tag::Item) => {
+                items += 1;
+
+                if separator.is_empty() {
+                    separator = LINE_BREAK;
+                }
+
+                continue;
+            }
+            Event::End(TagEnd::Item) => {
+                items -= 1;
+
+                continue;
+            }
+            // Lists nested in the items of another list continue it.
+            Event::Start(Tag::List(_)) if items == 0 => {
+                separator = BLANK_LINE;
+
+                continue;
+            }
+            Event::Start(
+                Tag::Paragraph | Tag::Heading { .. } | Tag::CodeBlock(_) | Tag::HtmlBlock,
+            ) => {
+                separator = BLANK_LINE;
+
+                continue;
+            }
+            // The line break ending the last line of a code block ends the
+            // block.
+            Event::End(TagEnd::CodeBlock) => {
+                if text.ends_with(NEWLINE) {
+                    text.pop();
+                }
+
+                continue;
+            }
+            Event::Code(content) | Event::Text(content) => content,
+            Event::HardBreak | Event::SoftBreak => LINE_BREAK.into(),
+            _ => continue,
+        };
+
+        // Separators are only written between text, so blocks without any
+        // don't leave blank lines behind.
+        if !text.is_empty() {
+            text.push_str(separator);
+        }
+
+        separator = "";
+        text.push_str(&content);
+    }
+
+    text
+}